@@ -0,0 +1,96 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::{ext::IdentExt, spanned::Spanned, Ident};
+
+use crate::model::prelude::Prelude;
+
+use super::{
+    chain::BrowsingChain,
+    output::{has_phantom_field, produce_inherent_impl_header_for_output, section_fields},
+    prelude::produce_flat_item_prelude,
+    trunk::FlatteningResult,
+};
+
+// ⟨?attr⟩ impl ⟨generics⟩ ⟨name⟩ ⟨generics⟩ ⟨where_clause⟩ {
+//     pub fn ⟨getter1⟩(&self) -> &⟨ty1⟩ { &self.⟨field1⟩ }
+//     …
+//     pub fn ⟨getterN⟩(&self) -> &⟨tyN⟩ { &self.⟨fieldN⟩ }
+//     pub fn into_parts(self) -> (⟨ty1⟩, …, ⟨tyN⟩,) { (self.⟨field1⟩, …, self.⟨fieldN⟩,) }
+//     pub fn from_parts(parts: (⟨ty1⟩, …, ⟨tyN⟩,)) -> Self {
+//         let (⟨field1⟩, …, ⟨fieldN⟩,) = parts;
+//         Self { ⟨field1⟩, …, ⟨fieldN⟩, }
+//     }
+// }
+//
+// where fields are listed in declaration order, getters of repeated sections are suffixed by
+// `_list` and arguments declared with patterns have no getter.
+//
+// Getters share the namespace of the methods continuing the chain, listed in `methods`.
+pub fn produce_accessors(
+    prelude: &Prelude,
+    chain: &BrowsingChain,
+    methods: &[&Ident],
+    tokens: &mut TokenStream,
+) -> FlatteningResult {
+    if !prelude.options.accessors {
+        return Ok(());
+    }
+    let mut sections = chain.into_iter().collect::<Vec<_>>();
+    sections.reverse();
+    let mut fields = Vec::new();
+    let mut getters = Vec::new();
+    for current in sections {
+        let current_fields = section_fields(chain, current);
+        if current.section().is_repeated() {
+            let getter = format_ident!("{}_list", current.section().ident.unraw());
+            getters.extend(
+                current_fields
+                    .iter()
+                    .map(|(ident, ty)| (getter.to_token_stream(), ident.clone(), ty.clone())),
+            );
+        } else {
+            for (arg, (ident, ty)) in current.fields().zip(&current_fields) {
+                if arg.pattern().is_none() {
+                    getters.push((ident.clone(), ident.clone(), ty.clone()));
+                }
+            }
+        }
+        fields.extend(current_fields);
+    }
+    for (getter, _, _) in &getters {
+        let name = getter.to_string();
+        if methods.iter().any(|method| *method == &name) {
+            let message =
+                format!("cannot generate an accessor for `{name}`, as a method of the same name continues the method chain");
+            let span = getter.span();
+            return Err(quote_spanned! { span => compile_error!(#message); });
+        }
+    }
+
+    let getter_names = getters.iter().map(|(getter, _, _)| getter);
+    let getter_fields = getters.iter().map(|(_, ident, _)| ident);
+    let getter_types = getters.iter().map(|(_, _, ty)| ty);
+    let idents = fields.iter().map(|(ident, _)| ident).collect::<Vec<_>>();
+    let types = fields.iter().map(|(_, ty)| ty).collect::<Vec<_>>();
+    let phantom = has_phantom_field(chain).then(|| quote! { _self: ::core::marker::PhantomData, });
+    produce_flat_item_prelude(prelude, chain, tokens);
+    produce_inherent_impl_header_for_output(chain, tokens);
+    quote! {
+        {
+            #(
+                pub fn #getter_names(&self) -> &#getter_types {
+                    &self.#getter_fields
+                }
+            )*
+            pub fn into_parts(self) -> (#(#types,)*) {
+                (#(self.#idents,)*)
+            }
+            pub fn from_parts(parts: (#(#types,)*)) -> Self {
+                let (#(#idents,)*) = parts;
+                Self { #(#idents,)* #phantom }
+            }
+        }
+    }
+    .to_tokens(tokens);
+    Ok(())
+}
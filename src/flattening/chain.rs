@@ -1,81 +1,317 @@
 use proc_macro2::TokenStream;
+use quote::quote_spanned;
 use std::result::Result;
+use syn::{spanned::Spanned, visit_mut::VisitMut, Ident, ReturnType};
 
-use super::{ordered_gens::OrderedGenericList, usual_args::UsualArg};
-use crate::model::section::Section;
+use super::{
+    impl_traits::ImplTraitDesugarer,
+    lifetimes::{ElidedLifetimeNamer, ElidedOutputLifetime},
+    naming::{output_type_ident, root_module_ident, root_type_prefix},
+    ordered_gens::OrderedGenericList,
+    self_type::SelfTypeReplacer,
+    usual_args::UsualArg,
+};
+use crate::model::{
+    branch::{Branch, BranchTail, IntermediateBody},
+    config::GenerationConfig,
+    options::ChainOptions,
+    section::Section,
+};
 
 #[derive(Clone, Copy)]
 pub struct RootImplHeader<'a> {
     pub generics: &'a syn::Generics,
     pub root_type: &'a syn::Type,
+    pub trait_ident: Option<&'a syn::Ident>,
+    pub implements_trait: bool,
 }
 
 enum BrowsingChainLink<'a> {
     Previous(&'a BrowsingChain<'a>),
+    Skipping(Box<BrowsingChain<'a>>),
     RootImpl(RootImplHeader<'a>),
     Beginning,
 }
 
 pub struct BrowsingChain<'a> {
+    config: &'a GenerationConfig,
+    options: &'a ChainOptions,
     depth: usize,
     section: &'a Section,
     args: Vec<UsualArg>,
+    derived: Vec<UsualArg>,
+    has_body: bool,
+    own_generics: syn::Generics,
     gen_list: OrderedGenericList,
     link: BrowsingChainLink<'a>,
+    skipped: bool,
 }
 
 impl<'a> BrowsingChain<'a> {
-    pub fn new(section: &'a Section) -> Result<BrowsingChain<'a>, TokenStream> {
-        Self::create(BrowsingChainLink::Beginning, section)
+    pub fn new(
+        config: &'a GenerationConfig,
+        options: &'a ChainOptions,
+        branch: &'a Branch,
+    ) -> Result<BrowsingChain<'a>, TokenStream> {
+        let link = BrowsingChainLink::Beginning;
+        Self::create_from_branch(config, options, link, branch)
     }
 
     pub fn new_root_impl(
+        config: &'a GenerationConfig,
+        options: &'a ChainOptions,
         generics: &'a syn::Generics,
         root_type: &'a syn::Type,
-        section: &'a Section,
+        implements_trait: bool,
+        branch: &'a Branch,
     ) -> Result<BrowsingChain<'a>, TokenStream> {
-        Self::create(
+        Self::create_from_branch(
+            config,
+            options,
             BrowsingChainLink::RootImpl(RootImplHeader {
                 generics,
                 root_type,
+                trait_ident: None,
+                implements_trait,
             }),
-            section,
+            branch,
+        )
+    }
+
+    // Inside a trait definition, `root_type` is a type parameter standing for `Self`.
+    pub fn new_root_trait(
+        config: &'a GenerationConfig,
+        options: &'a ChainOptions,
+        generics: &'a syn::Generics,
+        root_type: &'a syn::Type,
+        trait_ident: &'a syn::Ident,
+        branch: &'a Branch,
+    ) -> Result<BrowsingChain<'a>, TokenStream> {
+        Self::create_from_branch(
+            config,
+            options,
+            BrowsingChainLink::RootImpl(RootImplHeader {
+                generics,
+                root_type,
+                trait_ident: Some(trait_ident),
+                implements_trait: false,
+            }),
+            branch,
         )
     }
 
-    pub fn concat(&'a self, section: &'a Section) -> Result<BrowsingChain<'a>, TokenStream> {
-        Self::create(BrowsingChainLink::Previous(self), section)
+    pub fn concat(&'a self, branch: &'a Branch) -> Result<BrowsingChain<'a>, TokenStream> {
+        let link = BrowsingChainLink::Previous(self);
+        Self::create_from_branch(self.config, self.options, link, branch)
+    }
+
+    // Same as `concat` except that the optional sections in `skipped` are inserted in between,
+    // flagged as not being called.
+    pub fn concat_skipping(
+        &'a self,
+        skipped: &[&'a Section],
+        branch: &'a Branch,
+    ) -> Result<BrowsingChain<'a>, TokenStream> {
+        let Some((first_skipped, other_skipped)) = skipped.split_first() else {
+            return self.concat(branch);
+        };
+        let link = BrowsingChainLink::Previous(self);
+        let mut current = Self::create(
+            self.config,
+            self.options,
+            link,
+            first_skipped,
+            None,
+            false,
+            true,
+        )?;
+        for skipped_section in other_skipped {
+            let link = BrowsingChainLink::Skipping(Box::new(current));
+            current = Self::create(
+                self.config,
+                self.options,
+                link,
+                skipped_section,
+                None,
+                false,
+                true,
+            )?;
+        }
+        let link = BrowsingChainLink::Skipping(Box::new(current));
+        Self::create_from_branch(self.config, self.options, link, branch)
+    }
+
+    fn create_from_branch(
+        config: &'a GenerationConfig,
+        options: &'a ChainOptions,
+        link: BrowsingChainLink<'a>,
+        branch: &'a Branch,
+    ) -> Result<BrowsingChain<'a>, TokenStream> {
+        let body = intermediate_body(branch);
+        let is_final = branch.tail.is_final();
+        let chain = Self::create(
+            config,
+            options,
+            link,
+            &branch.section,
+            body,
+            is_final,
+            false,
+        )?;
+        match &branch.tail {
+            BranchTail::Leaf { output, .. } | BranchTail::Declaration { output, .. } => {
+                chain.check_output_lifetime(output)?;
+            }
+            BranchTail::Alternative { .. } => {}
+        }
+        Ok(chain)
     }
 
     fn create(
+        config: &'a GenerationConfig,
+        options: &'a ChainOptions,
         link: BrowsingChainLink<'a>,
         section: &'a Section,
+        body: Option<&'a IntermediateBody>,
+        is_final: bool,
+        skipped: bool,
     ) -> Result<BrowsingChain<'a>, TokenStream> {
-        let args = UsualArg::extract_usual_args(&section.inputs)?;
-        let depth = match link {
+        let mut args = UsualArg::extract_usual_args(section, &config.receiver)?;
+        let has_body = body.is_some();
+        let mut derived = match body {
+            Some(body) => UsualArg::extract_derived_values(body)?,
+            None => Vec::new(),
+        };
+        let root_type = match &link {
+            BrowsingChainLink::Previous(previous) => previous.root_type(),
+            BrowsingChainLink::Skipping(previous) => previous.root_type(),
+            BrowsingChainLink::RootImpl(header) => Some(header.root_type),
+            BrowsingChainLink::Beginning => None,
+        };
+        if let Some(root_type) = root_type {
+            let mut replacer = SelfTypeReplacer::new(root_type);
+            for arg in args.iter_mut().chain(derived.iter_mut()) {
+                arg.replace_self_type(&mut replacer);
+            }
+        }
+        let mut namer = ElidedLifetimeNamer::new(&section.ident);
+        for arg in &mut args {
+            arg.name_elided_lifetimes(&mut namer);
+        }
+        let elided_lifetimes = namer.into_lifetimes();
+        // The arguments of a final section are not stored, so they can keep their `impl Trait`
+        // types and the method its explicit generic arguments.
+        let mut desugarer = ImplTraitDesugarer::new(&section.ident);
+        if !is_final {
+            for arg in &mut args {
+                arg.desugar_impl_traits(&mut desugarer);
+            }
+        }
+        let impl_trait_params = desugarer.into_params();
+        if let (true, Some(param)) = (section.is_skippable(), impl_trait_params.first()) {
+            let message = "sections that can be skipped cannot take `impl Trait` arguments";
+            let span = param.bounds.span();
+            return Err(quote_spanned! { span => compile_error!(#message); });
+        }
+        let mut generics = section.generics.clone();
+        if let Some(root_type) = root_type {
+            SelfTypeReplacer::new(root_type).visit_generics_mut(&mut generics);
+        }
+        for (n, lifetime) in elided_lifetimes.iter().enumerate() {
+            let param = syn::LifetimeParam::new(lifetime.clone());
+            generics
+                .params
+                .insert(n, syn::GenericParam::Lifetime(param));
+        }
+        for param in impl_trait_params {
+            generics.params.push(syn::GenericParam::Type(param));
+        }
+        let depth = match &link {
             BrowsingChainLink::Previous(previous) => previous.depth + 1,
+            BrowsingChainLink::Skipping(previous) => previous.depth + 1,
             _ => 0,
         };
-        let gen_list = match link {
+        let gen_list = match &link {
             BrowsingChainLink::Previous(previous) => {
-                OrderedGenericList::augment(Some(&previous.gen_list), &section.generics)
+                OrderedGenericList::augment(Some(&previous.gen_list), &generics)
+            }
+            BrowsingChainLink::Skipping(previous) => {
+                // Generic parameters of the skipped sections must be declared by this one.
+                let mut gen_list = OrderedGenericList::augment(Some(&previous.gen_list), &generics);
+                gen_list.include_last_generics_of(&previous.gen_list);
+                gen_list
             }
             BrowsingChainLink::RootImpl(header) => {
-                let previous = OrderedGenericList::augment(None, header.generics);
-                OrderedGenericList::augment(Some(&previous), &section.generics)
+                let mut header_generics = header.generics.clone();
+                SelfTypeReplacer::new(header.root_type).visit_generics_mut(&mut header_generics);
+                let previous = OrderedGenericList::augment(None, &header_generics);
+                OrderedGenericList::augment(Some(&previous), &generics)
             }
-            _ => OrderedGenericList::augment(None, &section.generics),
+            _ => OrderedGenericList::augment(None, &generics),
+        };
+        let gen_list = match section.max_repetitions() {
+            Some(_) if !skipped => gen_list.with_counter(),
+            _ => gen_list,
         };
         let chain = BrowsingChain {
+            config,
+            options,
             link,
             section,
             args,
+            derived,
+            has_body,
+            own_generics: generics,
             gen_list,
             depth,
+            skipped,
         };
+        chain.check_derived_values()?;
         Ok(chain)
     }
 
+    // Values derived by an intermediate body are stored next to the arguments, so their names
+    // must not collide.
+    fn check_derived_values(&self) -> Result<(), TokenStream> {
+        let name_of = |arg: &UsualArg| {
+            let mut ident = TokenStream::new();
+            arg.push_ident_to_tokens(&mut ident);
+            ident
+        };
+        let mut names = self
+            .into_iter()
+            .skip(1)
+            .flat_map(|current| current.fields())
+            .chain(self.args.iter())
+            .map(|arg| name_of(arg).to_string())
+            .collect::<Vec<_>>();
+        for value in &self.derived {
+            let ident = name_of(value);
+            let name = ident.to_string();
+            if names.contains(&name) {
+                let message = format!("`{name}` is already stored by the method chain");
+                let span = ident.span();
+                return Err(quote_spanned! { span => compile_error!(#message); });
+            }
+            names.push(name);
+        }
+        Ok(())
+    }
+
+    // As in ordinary functions, an elided lifetime in the return type cannot be chosen among
+    // several lifetimes of the arguments.
+    fn check_output_lifetime(&'a self, output: &ReturnType) -> Result<(), TokenStream> {
+        if self.output_lifetime().is_some() || self.input_lifetimes().nth(1).is_none() {
+            return Ok(());
+        }
+        if !ElidedOutputLifetime::is_elided_in(output) {
+            return Ok(());
+        }
+        let message = "missing lifetime specifier: the return type borrows from one of several arguments of the method chain, so its lifetime must be named";
+        let span = output.span();
+        Err(quote_spanned! { span => compile_error!(#message); })
+    }
+
     pub fn depth(&self) -> usize {
         self.depth
     }
@@ -84,31 +320,142 @@ impl<'a> BrowsingChain<'a> {
         self.section
     }
 
+    pub fn config(&self) -> &'a GenerationConfig {
+        self.config
+    }
+
+    pub fn options(&self) -> &'a ChainOptions {
+        self.options
+    }
+
+    pub fn is_flat(&self) -> bool {
+        self.options.is_flat()
+    }
+
+    // The name of the type returned by this section.
+    pub fn output_ident(&'a self) -> Ident {
+        let mut sections = self
+            .into_iter()
+            .filter(|current| !current.is_skipped())
+            .map(|current| current.section)
+            .collect::<Vec<_>>();
+        sections.reverse();
+        let root_name = root_type_prefix(self.root_header());
+        output_type_ident(self.options, root_name.as_deref(), &sections)
+    }
+
+    // The name of the module holding the items of this section in the nested layout.
+    pub fn module_ident(&'a self) -> Ident {
+        match self.depth {
+            0 => root_module_ident(self.root_header(), &self.section.ident),
+            _ => self.section.ident.clone(),
+        }
+    }
+
     pub fn args(&self) -> &Vec<UsualArg> {
         &self.args
     }
 
+    // Arguments followed by the values derived by the intermediate body of the section.
+    pub fn fields(&self) -> impl Iterator<Item = &UsualArg> {
+        self.args.iter().chain(self.derived.iter())
+    }
+
+    pub fn derived(&self) -> &Vec<UsualArg> {
+        &self.derived
+    }
+
+    // Whether the section has an intermediate body, which may consume the stored values.
+    pub fn has_body(&self) -> bool {
+        self.has_body
+    }
+
     pub fn generics(&self) -> &OrderedGenericList {
         &self.gen_list
     }
 
+    // Generic parameters declared by this section, including named elided lifetimes.
+    pub fn own_generics(&self) -> &syn::Generics {
+        &self.own_generics
+    }
+
+    pub fn is_in_trait(&'a self) -> bool {
+        self.root_header()
+            .is_some_and(|header| header.trait_ident.is_some())
+    }
+
+    // Whether this section is an optional one that was not called.
+    pub fn is_skipped(&self) -> bool {
+        self.skipped
+    }
+
     pub fn previous(&'a self) -> Option<&'a BrowsingChain<'a>> {
-        match self.link {
+        match &self.link {
             BrowsingChainLink::Previous(previous) => Some(previous),
+            BrowsingChainLink::Skipping(previous) => Some(previous),
             _ => None,
         }
     }
 
+    // The type that `Self` designates in the method chain, if declared in an implementation.
+    pub fn root_type(&self) -> Option<&'a syn::Type> {
+        match &self.link {
+            BrowsingChainLink::Previous(previous) => previous.root_type(),
+            BrowsingChainLink::Skipping(previous) => previous.root_type(),
+            BrowsingChainLink::RootImpl(header) => Some(header.root_type),
+            BrowsingChainLink::Beginning => None,
+        }
+    }
+
+    // Whether the receiver of the first section is stored, so that `self` no longer designates it.
+    pub fn stores_receiver(&'a self) -> bool {
+        self.into_iter()
+            .skip(1)
+            .any(|previous| previous.args().iter().any(UsualArg::is_receiver))
+    }
+
     pub fn root_header(&'a self) -> Option<RootImplHeader<'a>> {
-        match self.link {
+        match &self.link {
             BrowsingChainLink::Previous(previous) => previous.root_header(),
-            BrowsingChainLink::RootImpl(header) => Some(header),
+            BrowsingChainLink::Skipping(previous) => previous.root_header(),
+            BrowsingChainLink::RootImpl(header) => Some(*header),
             _ => None,
         }
     }
 
+    // The lifetime given to references elided in the return type of a leaf section, following
+    // the usual elision rules: the lifetime of a `&self` receiver, or else the only lifetime
+    // appearing in the arguments.
+    pub fn output_lifetime(&'a self) -> Option<&'a syn::Lifetime> {
+        let receiver_lifetime = self
+            .into_iter()
+            .flat_map(|current| current.args.iter())
+            .find_map(|arg| arg.receiver_lifetime());
+        if receiver_lifetime.is_some() {
+            return receiver_lifetime;
+        }
+        let mut lifetimes = self.input_lifetimes();
+        match (lifetimes.next(), lifetimes.next()) {
+            (Some(lifetime), None) => Some(lifetime),
+            _ => None,
+        }
+    }
+
+    // Lifetimes declared or elided by the sections of the method chain.
+    fn input_lifetimes(&'a self) -> impl Iterator<Item = &'a syn::Lifetime> {
+        self.into_iter().flat_map(|current| {
+            current
+                .own_generics
+                .lifetimes()
+                .map(|param| &param.lifetime)
+        })
+    }
+
     pub fn is_last(&self) -> bool {
-        !matches!(&self.link, BrowsingChainLink::Previous(..))
+        matches!(
+            &self.link,
+            BrowsingChainLink::RootImpl(..) | BrowsingChainLink::Beginning
+        )
     }
 }
 
@@ -139,3 +486,10 @@ impl<'a> Iterator for BrowsingChainIterator<'a> {
         }
     }
 }
+
+fn intermediate_body(branch: &Branch) -> Option<&IntermediateBody> {
+    match &branch.tail {
+        BranchTail::Alternative { body, .. } => body.as_ref(),
+        _ => None,
+    }
+}
@@ -0,0 +1,111 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::Ident;
+
+use crate::model::prelude::Prelude;
+
+use super::{
+    chain::BrowsingChain,
+    output::{has_phantom_field, output_fields, produce_field_bounds},
+    prelude::produce_flat_item_prelude,
+};
+
+// ⟨derived_impl1⟩
+// …
+// ⟨derived_implN⟩
+pub fn produce_derived_impls(prelude: &Prelude, chain: &BrowsingChain, tokens: &mut TokenStream) {
+    for derive in &prelude.options.derives {
+        produce_flat_item_prelude(prelude, chain, tokens);
+        produce_derived_impl(derive, chain, tokens);
+    }
+}
+
+// impl ⟨generics⟩ ⟨trait⟩ for ⟨name⟩ ⟨generics⟩ where ⟨where_clause⟩, ⟨field_bounds⟩ {
+//     ⟨trait_items⟩
+// }
+fn produce_derived_impl(derive: &Ident, chain: &BrowsingChain, tokens: &mut TokenStream) {
+    let fields = output_fields(chain);
+    let output_ident = chain.output_ident();
+    let output_name = output_ident.to_string();
+    let idents = fields.iter().map(|(ident, _)| ident).collect::<Vec<_>>();
+    let trait_path = derived_trait_path(derive);
+    let items = match derive.to_string().as_str() {
+        "Clone" => {
+            let phantom =
+                has_phantom_field(chain).then(|| quote! { _self: ::core::marker::PhantomData, });
+            quote! {
+                fn clone(&self) -> Self {
+                    Self { #(#idents: ::core::clone::Clone::clone(&self.#idents),)* #phantom }
+                }
+            }
+        }
+        "Debug" => quote! {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.debug_struct(#output_name)
+                    #(.field(::core::stringify!(#idents), &self.#idents))*
+                    .finish()
+            }
+        },
+        "PartialEq" => quote! {
+            fn eq(&self, other: &Self) -> bool {
+                true #(&& ::core::cmp::PartialEq::eq(&self.#idents, &other.#idents))*
+            }
+        },
+        "Hash" => quote! {
+            fn hash<__H: ::core::hash::Hasher>(&self, state: &mut __H) {
+                #(::core::hash::Hash::hash(&self.#idents, state);)*
+            }
+        },
+        "PartialOrd" => quote! {
+            fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+                #(
+                    match ::core::cmp::PartialOrd::partial_cmp(&self.#idents, &other.#idents) {
+                        ::core::option::Option::Some(::core::cmp::Ordering::Equal) => {}
+                        ordering => return ordering,
+                    }
+                )*
+                ::core::option::Option::Some(::core::cmp::Ordering::Equal)
+            }
+        },
+        "Ord" => quote! {
+            fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+                #(
+                    match ::core::cmp::Ord::cmp(&self.#idents, &other.#idents) {
+                        ::core::cmp::Ordering::Equal => {}
+                        ordering => return ordering,
+                    }
+                )*
+                ::core::cmp::Ordering::Equal
+            }
+        },
+        // Marker traits such as `Copy` and `Eq` have no items.
+        _ => TokenStream::new(),
+    };
+
+    let span = Span::call_site();
+    syn::token::Impl { span }.to_tokens(tokens);
+    chain
+        .generics()
+        .produce_complete_constrained_generics(tokens);
+    trait_path.to_tokens(tokens);
+    syn::token::For { span }.to_tokens(tokens);
+    output_ident.to_tokens(tokens);
+    chain
+        .generics()
+        .produce_complete_generic_names(false, tokens);
+    let predicates = produce_field_bounds(chain, &trait_path);
+    chain
+        .generics()
+        .produce_extended_where_clause(predicates, tokens);
+    quote! { { #items } }.to_tokens(tokens);
+}
+
+fn derived_trait_path(derive: &Ident) -> TokenStream {
+    match derive.to_string().as_str() {
+        "Clone" => quote! { ::core::clone::Clone },
+        "Copy" => quote! { ::core::marker::Copy },
+        "Debug" => quote! { ::core::fmt::Debug },
+        "Hash" => quote! { ::core::hash::Hash },
+        _ => quote! { ::core::cmp::#derive },
+    }
+}
@@ -1,21 +1,35 @@
 use proc_macro2::TokenStream;
 
-use crate::model::branch::{BranchTail, DocumentedBranch};
-
-use super::chain::{BrowsingChain, RootImplHeader};
+use crate::model::{
+    branch::{BranchTail, DocumentedBranch},
+    options::ChainOptions,
+    section::Section,
+};
+
+use super::{
+    chain::{BrowsingChain, RootImplHeader},
+    naming::{output_type_ident, root_module_ident, root_type_name, root_type_prefix},
+};
 use quote::{quote, ToTokens};
 
 pub fn produce_linked_doc_for_module<'a>(
     chain: &'a BrowsingChain<'a>,
-    tail: &BranchTail,
+    tail: &'a BranchTail,
     tokens: &mut TokenStream,
 ) {
-    let mut fn_names = Vec::<String>::new();
-    let root_header = collect_fn_names_and_root_header(chain, &mut fn_names);
+    let options = chain.options();
+    let mut sections = Vec::<&Section>::new();
+    let root_header = collect_sections_and_root_header(chain, &mut sections);
 
     let mut intro = "Intermediary module for partial method chain ".to_string();
-    let localization = fn_names.len() - 1;
-    produce_doc_for_sequence(root_header, fn_names.as_slice(), localization, &mut intro);
+    let localization = sections.len() - 1;
+    produce_doc_for_sequence(
+        options,
+        root_header,
+        sections.as_slice(),
+        localization,
+        &mut intro,
+    );
 
     quote! {
         #[doc = #intro]
@@ -25,87 +39,125 @@ pub fn produce_linked_doc_for_module<'a>(
     .to_tokens(tokens);
 
     if let BranchTail::Alternative { rest, .. } = tail {
-        produce_doc_for_all_sequences(&rest.0, root_header, &mut fn_names, localization, tokens);
-        for branch in &rest.1 {
-            produce_doc_for_all_sequences(branch, root_header, &mut fn_names, localization, tokens);
+        for branch in std::iter::once(&rest.0).chain(&rest.1) {
+            let context = DocContext {
+                options,
+                root_header,
+                localization,
+            };
+            produce_doc_for_all_sequences(branch, context, &mut sections, tokens);
         }
     }
 }
 
 pub fn produce_linked_doc_for_output<'a>(chain: &'a BrowsingChain<'a>, tokens: &mut TokenStream) {
-    let mut fn_names = Vec::<String>::new();
-    let root_header = collect_fn_names_and_root_header(chain, &mut fn_names);
+    let mut sections = Vec::<&Section>::new();
+    let root_header = collect_sections_and_root_header(chain, &mut sections);
 
     let mut comment = "Intermediary type returned by partial method chain ".to_string();
     produce_doc_for_sequence(
+        chain.options(),
         root_header,
-        fn_names.as_slice(),
-        fn_names.len(),
+        sections.as_slice(),
+        sections.len(),
         &mut comment,
     );
 
     quote! { #[doc = #comment] }.to_tokens(tokens);
 }
 
-fn collect_fn_names_and_root_header<'a>(
+fn collect_sections_and_root_header<'a>(
     chain: &'a BrowsingChain<'a>,
-    fn_names: &mut Vec<String>,
+    sections: &mut Vec<&'a Section>,
 ) -> Option<RootImplHeader<'a>> {
     let root_header;
     if let Some(previous) = chain.previous() {
-        root_header = collect_fn_names_and_root_header(previous, fn_names);
+        root_header = collect_sections_and_root_header(previous, sections);
     } else if let Some(header) = chain.root_header() {
         root_header = Some(header);
     } else {
         root_header = None;
     }
-    fn_names.push(chain.section().ident.to_string());
+    if !chain.is_skipped() {
+        sections.push(chain.section());
+    }
     root_header
 }
 
-fn produce_doc_for_all_sequences(
-    branch: &DocumentedBranch,
-    root_header: Option<RootImplHeader>,
-    fn_names: &mut Vec<String>,
+#[derive(Clone, Copy)]
+struct DocContext<'a> {
+    options: &'a ChainOptions,
+    root_header: Option<RootImplHeader<'a>>,
     localization: usize,
+}
+
+fn produce_doc_for_all_sequences<'a>(
+    branch: &'a DocumentedBranch,
+    context: DocContext,
+    sections: &mut Vec<&'a Section>,
     tokens: &mut TokenStream,
 ) {
-    let fn_name = branch.branch.section.ident.to_string();
-    fn_names.push(fn_name);
+    sections.push(&branch.branch.section);
 
     match &branch.branch.tail {
         BranchTail::Alternative { rest, .. } => {
-            produce_doc_for_all_sequences(&rest.0, root_header, fn_names, localization, tokens);
-            for branch in &rest.1 {
-                produce_doc_for_all_sequences(branch, root_header, fn_names, localization, tokens);
+            for branch in std::iter::once(&rest.0).chain(&rest.1) {
+                produce_doc_for_all_sequences(branch, context, sections, tokens);
             }
         }
-        BranchTail::Leaf { .. } => {
+        BranchTail::Leaf { .. } | BranchTail::Declaration { .. } => {
             let mut item = "- ".to_string();
-            produce_doc_for_sequence(root_header, fn_names.as_slice(), localization, &mut item);
-            quote! { #[doc = #item] }.to_tokens(tokens);
+            produce_doc_for_sequence(
+                context.options,
+                context.root_header,
+                sections.as_slice(),
+                context.localization,
+                &mut item,
+            );
+            let predicates = sections[context.localization + 1..]
+                .iter()
+                .flat_map(|section| section.attr_block.cfgs())
+                .filter_map(|cfg| cfg.meta.require_list().ok())
+                .map(|list| &list.tokens)
+                .collect::<Vec<_>>();
+            if predicates.is_empty() {
+                quote! { #[doc = #item] }.to_tokens(tokens);
+            } else {
+                // Method chains going through disabled sections are left out.
+                quote! { #[cfg_attr(all(#(#predicates),*), doc = #item)] }.to_tokens(tokens);
+            }
         }
     }
 
-    fn_names.pop();
+    sections.pop();
+
+    if branch.branch.section.is_skippable() {
+        if let BranchTail::Alternative { rest, .. } = &branch.branch.tail {
+            for branch in std::iter::once(&rest.0).chain(&rest.1) {
+                produce_doc_for_all_sequences(branch, context, sections, tokens);
+            }
+        }
+    }
 }
 
+// Links are relative to the module of the type or module being documented, whose sections are
+// the first `localisation` ones. In the flat layout, they are all relative to the root scope.
 fn produce_doc_for_sequence(
+    options: &ChainOptions,
     root_header: Option<RootImplHeader>,
-    fn_names: &[String],
+    sections: &[&Section],
     localisation: usize,
     doc: &mut String,
 ) {
-    let mut root_type_name: Option<String> = None;
-    if let Some(RootImplHeader {
-        root_type: syn::Type::Path(p),
-        ..
-    }) = root_header
-    {
-        if let Some(segment) = p.path.segments.last() {
-            root_type_name = Some(segment.ident.to_string());
-        }
+    let fn_names = sections
+        .iter()
+        .map(|section| section.ident.to_string())
+        .collect::<Vec<_>>();
+    let mut module_names = fn_names.clone();
+    if let Some(first) = sections.first() {
+        module_names[0] = root_module_ident(root_header, &first.ident).to_string();
     }
+    let root_type_name = root_type_name(root_header);
     for (n, fn_name) in fn_names.iter().enumerate() {
         let is_function = n == 0 && root_header.is_none();
 
@@ -122,19 +174,15 @@ fn produce_doc_for_sequence(
         doc.push_str("`](");
         doc.push_str(if is_function { "fn@" } else { "method@" });
 
-        if n <= localisation {
-            for _ in 0..(localisation - n) {
-                doc.push_str("super::")
-            }
-        } else {
-            for fn_name in &fn_names[localisation..n] {
-                doc.push_str(fn_name.as_str());
-                doc.push_str("::");
-            }
+        if !options.is_flat() {
+            produce_relative_path(&module_names, localisation, n, doc);
         }
 
         if 0 < n {
-            doc.push_str("Output::");
+            let root_prefix = root_type_prefix(root_header);
+            let ident = output_type_ident(options, root_prefix.as_deref(), &sections[..n]);
+            doc.push_str(&ident.to_string());
+            doc.push_str("::");
         } else if let Some(root_type_name) = &root_type_name {
             doc.push_str(root_type_name.as_str());
             doc.push_str("::")
@@ -150,3 +198,17 @@ fn produce_doc_for_sequence(
         doc.push_str(".…`");
     }
 }
+
+// super::…::super:: ∨ ⟨module1⟩::…::⟨moduleN⟩::
+fn produce_relative_path(module_names: &[String], localisation: usize, n: usize, doc: &mut String) {
+    if n <= localisation {
+        for _ in 0..(localisation - n) {
+            doc.push_str("super::")
+        }
+    } else {
+        for module_name in &module_names[localisation..n] {
+            doc.push_str(module_name.as_str());
+            doc.push_str("::");
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use proc_macro2::Span;
+use syn::visit_mut::VisitMut;
+use syn::{Ident, Type, TypeParam};
+
+use super::naming::to_camel_case;
+
+// Replaces each `impl Trait` type inside the arguments of a section by a fresh type parameter,
+// so that the argument can be stored in an intermediate type.
+pub struct ImplTraitDesugarer {
+    prefix: String,
+    params: Vec<TypeParam>,
+}
+
+impl ImplTraitDesugarer {
+    pub fn new(section_ident: &Ident) -> Self {
+        Self {
+            prefix: format!("__{}Impl", to_camel_case(section_ident)),
+            params: Vec::new(),
+        }
+    }
+
+    pub fn into_params(self) -> Vec<TypeParam> {
+        self.params
+    }
+}
+
+impl VisitMut for ImplTraitDesugarer {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        syn::visit_mut::visit_type_mut(self, ty);
+        if let Type::ImplTrait(impl_trait) = ty {
+            let name = format!("{}{}", self.prefix, self.params.len());
+            let ident = Ident::new(name.as_str(), Span::call_site());
+            let mut param = TypeParam::from(ident.clone());
+            param.colon_token = Some(Default::default());
+            param.bounds = impl_trait.bounds.clone();
+            self.params.push(param);
+            *ty = syn::parse_quote! { #ident };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::{quote, ToTokens};
+    use syn::visit_mut::VisitMut;
+    use syn::{Ident, Type};
+
+    use super::ImplTraitDesugarer;
+
+    #[test]
+    fn desugar_impl_traits() {
+        let section_ident: Ident = syn::parse_quote!(with_filter);
+        let mut desugarer = ImplTraitDesugarer::new(&section_ident);
+
+        let mut ty: Type = syn::parse2(quote!((
+            impl FnMut(&u8) -> bool,
+            Vec<impl Iterator<Item = impl Copy>>
+        )))
+        .unwrap();
+        desugarer.visit_type_mut(&mut ty);
+
+        assert_eq!(
+            ty.to_token_stream().to_string(),
+            "(__WithFilterImpl0 , Vec < __WithFilterImpl2 >)"
+        );
+        let params = desugarer.into_params();
+        assert_eq!(
+            quote!(#(#params),*).to_string(),
+            "__WithFilterImpl0 : FnMut (& u8) -> bool , \
+             __WithFilterImpl1 : Copy , \
+             __WithFilterImpl2 : Iterator < Item = __WithFilterImpl1 >"
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use proc_macro2::Span;
+use syn::ext::IdentExt;
+use syn::visit_mut::VisitMut;
+use syn::{Ident, Lifetime, ParenthesizedGenericArguments, ReturnType, TypeBareFn, TypeReference};
+
+// Gives a fresh name to each lifetime elided inside the arguments of a section.
+pub struct ElidedLifetimeNamer {
+    prefix: String,
+    lifetimes: Vec<Lifetime>,
+}
+
+impl ElidedLifetimeNamer {
+    pub fn new(section_ident: &Ident) -> Self {
+        Self {
+            prefix: format!("'__{}_", section_ident.unraw()),
+            lifetimes: Vec::new(),
+        }
+    }
+
+    pub fn name(&mut self, lifetime: &mut Option<Lifetime>) {
+        if lifetime.is_none() {
+            *lifetime = Some(self.create());
+        }
+    }
+
+    pub fn into_lifetimes(self) -> Vec<Lifetime> {
+        self.lifetimes
+    }
+
+    fn create(&mut self) -> Lifetime {
+        let name = format!("{}{}", self.prefix, self.lifetimes.len());
+        let lifetime = Lifetime::new(name.as_str(), Span::call_site());
+        self.lifetimes.push(lifetime.clone());
+        lifetime
+    }
+}
+
+impl VisitMut for ElidedLifetimeNamer {
+    fn visit_type_reference_mut(&mut self, reference: &mut TypeReference) {
+        self.name(&mut reference.lifetime);
+        syn::visit_mut::visit_type_reference_mut(self, reference);
+    }
+
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        if lifetime.ident == "_" {
+            *lifetime = self.create();
+        }
+    }
+
+    // Lifetimes elided in function pointers and `Fn` traits belong to them.
+    fn visit_type_bare_fn_mut(&mut self, _: &mut TypeBareFn) {}
+
+    fn visit_parenthesized_generic_arguments_mut(&mut self, _: &mut ParenthesizedGenericArguments) {
+    }
+}
+
+// Replaces the lifetimes elided in the return type of a leaf section by `lifetime`.
+pub struct ElidedOutputLifetime<'a> {
+    lifetime: &'a Lifetime,
+    is_elided: bool,
+}
+
+impl<'a> ElidedOutputLifetime<'a> {
+    pub fn replace_in(lifetime: &'a Lifetime, output: &ReturnType) -> ReturnType {
+        let mut output = output.clone();
+        Self::new(lifetime).visit_return_type_mut(&mut output);
+        output
+    }
+
+    pub fn is_elided_in(output: &ReturnType) -> bool {
+        let lifetime = Lifetime::new("'_", Span::call_site());
+        let mut replacer = ElidedOutputLifetime::new(&lifetime);
+        replacer.visit_return_type_mut(&mut output.clone());
+        replacer.is_elided
+    }
+
+    fn new(lifetime: &'a Lifetime) -> Self {
+        Self {
+            lifetime,
+            is_elided: false,
+        }
+    }
+}
+
+impl VisitMut for ElidedOutputLifetime<'_> {
+    fn visit_type_reference_mut(&mut self, reference: &mut TypeReference) {
+        if reference.lifetime.is_none() {
+            reference.lifetime = Some(self.lifetime.clone());
+            self.is_elided = true;
+        }
+        syn::visit_mut::visit_type_reference_mut(self, reference);
+    }
+
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        if lifetime.ident == "_" {
+            *lifetime = self.lifetime.clone();
+            self.is_elided = true;
+        }
+    }
+
+    fn visit_type_bare_fn_mut(&mut self, _: &mut TypeBareFn) {}
+
+    fn visit_parenthesized_generic_arguments_mut(&mut self, _: &mut ParenthesizedGenericArguments) {
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::{quote, ToTokens};
+    use syn::visit_mut::VisitMut;
+    use syn::{Ident, Type};
+
+    use super::ElidedLifetimeNamer;
+
+    #[test]
+    fn name_elided_lifetimes() {
+        let section_ident: Ident = syn::parse_quote!(with);
+        let mut namer = ElidedLifetimeNamer::new(&section_ident);
+
+        let mut ty: Type = syn::parse2(quote!(&(&'a str, Cow<'_, str>, fn(&str) -> &str))).unwrap();
+        namer.visit_type_mut(&mut ty);
+
+        assert_eq!(
+            ty.to_token_stream().to_string(),
+            "& '__with_0 (& 'a str , Cow < '__with_1 , str > , fn (& str) -> & str)"
+        );
+        assert_eq!(2, namer.into_lifetimes().len());
+    }
+
+    #[test]
+    fn name_elided_lifetimes_of_raw_sections() {
+        let section_ident: Ident = syn::parse_quote!(r#type);
+        let mut namer = ElidedLifetimeNamer::new(&section_ident);
+
+        let mut ty: Type = syn::parse2(quote!(&str)).unwrap();
+        namer.visit_type_mut(&mut ty);
+
+        assert_eq!(ty.to_token_stream().to_string(), "& '__type_0 str");
+    }
+}
@@ -0,0 +1,377 @@
+use std::iter::once;
+
+use proc_macro2::TokenStream;
+use quote::{quote_spanned, ToTokens};
+
+use crate::model::{
+    attribute::DocumentationBlock,
+    branch::{BranchTail, DocumentedBranch},
+    prelude::Prelude,
+    section::Section,
+    tree::Tree,
+    trunk::{Trunk, TrunkAlternative},
+};
+
+use super::{chain::RootImplHeader, naming::root_module_ident};
+
+type MergingResult = std::result::Result<(), TokenStream>;
+
+// Position of a method chain in the tree: the index of its trunk, and its index among the
+// method chains of an `impl` or `trait` block.
+#[derive(Clone, Copy, PartialEq)]
+struct Occurrence {
+    trunk: usize,
+    fn_trunk: Option<usize>,
+}
+
+// Where the entry method of a method chain is declared.
+#[derive(PartialEq)]
+enum Context {
+    Root,
+    Block(usize),
+}
+
+impl Occurrence {
+    fn context(&self) -> Context {
+        match self.fn_trunk {
+            Some(_) => Context::Block(self.trunk),
+            None => Context::Root,
+        }
+    }
+
+    fn prelude<'a>(&self, roots: &'a [Trunk]) -> &'a Prelude {
+        let trunk = &roots[self.trunk];
+        match (&trunk.alternative, self.fn_trunk) {
+            (TrunkAlternative::Impl { fn_trunks, .. }, Some(n))
+            | (TrunkAlternative::Trait { fn_trunks, .. }, Some(n)) => &fn_trunks[n].prelude,
+            _ => &trunk.prelude,
+        }
+    }
+
+    fn documented<'a>(&self, roots: &'a [Trunk]) -> &'a DocumentedBranch {
+        match (&roots[self.trunk].alternative, self.fn_trunk) {
+            (TrunkAlternative::Fn { documented, .. }, _) => documented,
+            (TrunkAlternative::Impl { fn_trunks, .. }, Some(n))
+            | (TrunkAlternative::Trait { fn_trunks, .. }, Some(n)) => &fn_trunks[n].documented,
+            _ => unreachable!("method chains of blocks are always indexed"),
+        }
+    }
+
+    fn documented_mut<'a>(&self, roots: &'a mut [Trunk]) -> &'a mut DocumentedBranch {
+        match (&mut roots[self.trunk].alternative, self.fn_trunk) {
+            (TrunkAlternative::Fn { documented, .. }, _) => documented,
+            (TrunkAlternative::Impl { fn_trunks, .. }, Some(n))
+            | (TrunkAlternative::Trait { fn_trunks, .. }, Some(n)) => &mut fn_trunks[n].documented,
+            _ => unreachable!("method chains of blocks are always indexed"),
+        }
+    }
+}
+
+fn error(span: proc_macro2::Span, message: String) -> TokenStream {
+    quote_spanned! { span => compile_error!(#message); }
+}
+
+// Punctuation is left aside so that a trailing comma does not make a difference.
+fn produce_signature(section: &Section) -> String {
+    let mut tokens = section.generics.params.iter().fold(
+        section.ident.to_token_stream(),
+        |mut tokens, param| {
+            param.to_tokens(&mut tokens);
+            tokens
+        },
+    );
+    for arg in &section.inputs {
+        arg.to_tokens(&mut tokens);
+    }
+    section.question_token.to_tokens(&mut tokens);
+    if let Some(repetition) = &section.repetition {
+        repetition.star_token.to_tokens(&mut tokens);
+        if let Some(max) = &repetition.max {
+            max.value.to_tokens(&mut tokens);
+        }
+    }
+    if let Some(where_clause) = &section.generics.where_clause {
+        for predicate in &where_clause.predicates {
+            predicate.to_tokens(&mut tokens);
+        }
+    }
+    tokens.to_string()
+}
+
+fn check_same_signatures(section: &Section, other: &Section) -> MergingResult {
+    if produce_signature(section) == produce_signature(other) {
+        Ok(())
+    } else {
+        let message = format!(
+            "section `{}` is declared with different signatures in method chains sharing the same prefix",
+            other.ident
+        );
+        Err(error(other.ident.span(), message))
+    }
+}
+
+fn check_compatible_contexts(
+    roots: &[Trunk],
+    module: &str,
+    first: Occurrence,
+    other: Occurrence,
+    section: &Section,
+) -> MergingResult {
+    let name = &section.ident;
+    if first.context() != other.context() {
+        // Continuations of one block would otherwise leak into the other, which declares its own
+        // entry method as well.
+        let message = format!(
+            "method chains starting with `{name}` are declared in different blocks and would share the module `{module}`"
+        );
+        return Err(error(name.span(), message));
+    }
+    let first_prelude = first.prelude(roots);
+    let other_prelude = other.prelude(roots);
+    if first_prelude.options != other_prelude.options {
+        let message = format!(
+            "method chains starting with `{name}` must share the same `assemblist` options"
+        );
+        return Err(error(name.span(), message));
+    }
+    // A single entry method remains, so it must be declared the same way.
+    if first_prelude.to_token_stream().to_string() != other_prelude.to_token_stream().to_string() {
+        let message = format!(
+            "method chains starting with `{name}` must share the same attributes, visibility and asyncness"
+        );
+        return Err(error(name.span(), message));
+    }
+    Ok(())
+}
+
+// The first branch of an alternative inherits the documentation of its parent when it has none.
+// Once moved into another tree, this documentation must be carried explicitly.
+fn make_documentation_explicit(tail: &mut BranchTail, doc_block: &DocumentationBlock) {
+    if let BranchTail::Alternative { rest, .. } = tail {
+        if rest.0.doc_block.is_empty() {
+            rest.0.doc_block = doc_block.sections_after(1);
+        }
+        for branch in once(&mut rest.0).chain(rest.1.iter_mut()) {
+            make_documentation_explicit(&mut branch.branch.tail, &branch.doc_block);
+        }
+    }
+}
+
+// Adds the continuations of `other` to those of `tail`, merging branches sharing the same name.
+fn merge_tails(tail: &mut BranchTail, other: BranchTail, section: &Section) -> MergingResult {
+    let message = match (tail, other) {
+        (
+            BranchTail::Alternative { body, rest, .. },
+            BranchTail::Alternative {
+                body: other_body,
+                rest: other,
+                ..
+            },
+        ) => {
+            match (&body, other_body) {
+                (Some(body), Some(other_body))
+                    if body.to_token_stream().to_string()
+                        != other_body.to_token_stream().to_string() =>
+                {
+                    let message = format!(
+                        "section `{}` is declared with different bodies in method chains sharing the same prefix",
+                        section.ident
+                    );
+                    return Err(error(section.ident.span(), message));
+                }
+                (None, Some(other_body)) => *body = Some(other_body),
+                _ => {}
+            }
+            let (first, others) = *other;
+            for branch in once(first).chain(others) {
+                let existing = once(&mut rest.0)
+                    .chain(rest.1.iter_mut())
+                    .find(|existing| existing.branch.section.ident == branch.branch.section.ident);
+                match existing {
+                    Some(existing) => {
+                        let section = &branch.branch.section;
+                        check_same_signatures(&existing.branch.section, section)?;
+                        merge_tails(&mut existing.branch.tail, branch.branch.tail, section)?;
+                    }
+                    None => rest.1.push(branch),
+                }
+            }
+            return Ok(());
+        }
+        (tail, other) if tail.is_final() && other.is_final() => format!(
+            "a method chain ending with section `{}` is already declared",
+            section.ident
+        ),
+        _ => format!(
+            "section `{}` cannot both end a method chain and be followed by other sections",
+            section.ident
+        ),
+    };
+    Err(error(section.ident.span(), message))
+}
+
+fn collect_occurrences(roots: &[Trunk]) -> Vec<(String, Vec<Occurrence>)> {
+    let mut groups = Vec::<(String, Vec<Occurrence>)>::new();
+    let mut push = |name: String, occurrence: Occurrence| match groups
+        .iter_mut()
+        .find(|(existing, _)| *existing == name)
+    {
+        Some((_, occurrences)) => occurrences.push(occurrence),
+        None => groups.push((name, vec![occurrence])),
+    };
+    for (trunk, root) in roots.iter().enumerate() {
+        match &root.alternative {
+            TrunkAlternative::Fn { documented, .. } => {
+                let name = documented.branch.section.ident.to_string();
+                push(
+                    name,
+                    Occurrence {
+                        trunk,
+                        fn_trunk: None,
+                    },
+                );
+            }
+            TrunkAlternative::Impl {
+                header, fn_trunks, ..
+            } => {
+                // Each implementation of a trait has its own modules.
+                let root_header = RootImplHeader {
+                    generics: &header.generics,
+                    root_type: &header.self_ty,
+                    trait_ident: None,
+                    implements_trait: header.trait_.is_some(),
+                };
+                for (n, fn_trunk) in fn_trunks.iter().enumerate() {
+                    let section_ident = &fn_trunk.documented.branch.section.ident;
+                    let name = root_module_ident(Some(root_header), section_ident).to_string();
+                    let fn_trunk = Some(n);
+                    push(name, Occurrence { trunk, fn_trunk });
+                }
+            }
+            TrunkAlternative::Trait { fn_trunks, .. } => {
+                for (n, fn_trunk) in fn_trunks.iter().enumerate() {
+                    let name = fn_trunk.documented.branch.section.ident.to_string();
+                    let fn_trunk = Some(n);
+                    push(name, Occurrence { trunk, fn_trunk });
+                }
+            }
+        }
+    }
+    groups
+}
+
+// Method chains starting with the same section share a single tree of intermediate types.
+// Each of them is given the merged tree, and only one entry method remains per block.
+pub fn merge_shared_prefixes(tree: Tree) -> Result<Tree, TokenStream> {
+    let mut roots = tree.roots;
+    let mut redundant = Vec::<Occurrence>::new();
+    for (module, occurrences) in collect_occurrences(&roots) {
+        let (first, others) = occurrences.split_first().expect("groups are never empty");
+        if others.is_empty() {
+            continue;
+        }
+        let mut merged = first.documented(&roots).branch.clone();
+        for other in others {
+            let documented = other.documented(&roots).clone();
+            let section = &documented.branch.section;
+            check_same_signatures(&merged.section, section)?;
+            check_compatible_contexts(&roots, &module, *first, *other, section)?;
+            let mut tail = documented.branch.tail;
+            make_documentation_explicit(&mut tail, &documented.doc_block);
+            merge_tails(&mut merged.tail, tail, section)?;
+        }
+        for (n, occurrence) in occurrences.iter().enumerate() {
+            occurrence.documented_mut(&mut roots).branch.tail = merged.tail.clone();
+            let context = occurrence.context();
+            if occurrences[..n].iter().any(|o| o.context() == context) {
+                redundant.push(*occurrence);
+            }
+        }
+    }
+
+    let roots = roots
+        .into_iter()
+        .enumerate()
+        .filter(|(trunk, _)| {
+            !redundant.contains(&Occurrence {
+                trunk: *trunk,
+                fn_trunk: None,
+            })
+        })
+        .map(|(trunk, mut root)| {
+            if let TrunkAlternative::Impl { fn_trunks, .. }
+            | TrunkAlternative::Trait { fn_trunks, .. } = &mut root.alternative
+            {
+                let mut n = 0;
+                fn_trunks.retain(|_| {
+                    let fn_trunk = Some(n);
+                    n += 1;
+                    !redundant.contains(&Occurrence { trunk, fn_trunk })
+                });
+            }
+            root
+        })
+        .collect();
+    Ok(Tree {
+        config: tree.config,
+        roots,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+
+    use crate::{flattening::merge::merge_shared_prefixes, model::tree::Tree};
+
+    #[test]
+    fn merge_chains_sharing_prefixes() {
+        let tokens = quote!(
+            fn first(a: u8).second().third() {}
+            fn first(a: u8,).second().fourth() {}
+            fn first(a: u8).fifth() {}
+            impl Intro {
+                fn start(a: u8).sixth() {}
+                fn start(a: u8).seventh() {}
+            }
+        );
+
+        let tree = asserts::tokens_are_parsable_as::<Tree>(tokens);
+        let Ok(tree) = merge_shared_prefixes(tree) else {
+            panic!("Should not have failed");
+        };
+
+        asserts::equivalent!(
+            quote!(#tree).to_string().as_str(),
+            "fn first (a : u8) . {
+                fn second () . { fn third () { } fn fourth () { } }
+                fn fifth () { }
+            }
+            impl Intro {
+                fn start (a : u8) . { fn sixth () { } fn seventh () { } }
+            }"
+        );
+    }
+
+    #[test]
+    fn reject_conflicting_chains() {
+        let conflicts = [
+            quote!(fn first(a: u8).second() {} fn first(a: u16).third() {}),
+            quote!(fn first().second() {} fn first().second() {}),
+            quote!(fn first().second() {} fn first().second().third() {}),
+            quote!(fn first().second() {} pub fn first().third() {}),
+            quote!(fn first().second() {} impl Intro { fn first().third() {} }),
+            quote!(fn first().second() {} impl<T> Intro<T> { fn first().third() {} }),
+            quote!(impl Intro { fn first().second() {} } impl Other { fn first().third() {} }),
+            quote!(impl Intro { fn first().second() {} } impl Intro { fn first().third() {} }),
+            quote!(impl Intro { fn first(&self).second() {} } impl Other { fn first(&self).third() {} }),
+            quote!(fn vec_first().second() {} impl Intro for Vec { fn first().third() {} }),
+            quote!(impl Intro for Vec { fn first().second() {} } impl Other for Vec { fn first().third() {} }),
+        ];
+
+        for tokens in conflicts {
+            let tree = asserts::tokens_are_parsable_as::<Tree>(tokens);
+            assert!(merge_shared_prefixes(tree).is_err());
+        }
+    }
+}
@@ -1,33 +1,52 @@
 use proc_macro2::{Span, TokenStream};
-use quote::ToTokens;
-use syn::token::Brace;
+use quote::{quote, ToTokens};
+use syn::{
+    parse_quote, token::Brace, visit_mut::VisitMut, GenericArgument, Ident, PathArguments,
+    ReturnType, Type,
+};
 
-use crate::model::{attribute::DocumentationBlockView, branch::BranchTail, prelude::Prelude};
+use crate::model::{
+    attribute::DocumentationBlockView,
+    branch::{BranchTail, IntermediateBody},
+    prelude::Prelude,
+};
 
 use super::{
     chain::BrowsingChain,
+    lifetimes::ElidedOutputLifetime,
     output::{
-        produce_output_deconstruction, produce_output_instance, produce_output_name_with_namespace,
+        produce_field_bounds, produce_field_idents, produce_output_deconstruction,
+        produce_output_instance, produce_output_name_with_namespace, produce_phantom_field,
+        produce_repeated_element,
     },
-    prelude::produce_method_prelude,
+    prelude::{produce_flat_item_prelude, produce_method_prelude},
+    receiver::ReceiverReplacer,
+    self_tokens::SelfTokenReplacer,
+    self_type::SelfTypeReplacer,
+    trait_def::produce_trait_dispatch,
 };
 
 // #[inline]
-// pub fn ⟨name⟩⟨generics⟩(self, ⟨args⟩) -> ⟨name⟩::Output ⟨generics⟩ {
+// pub ⟨?async⟩ fn ⟨name⟩⟨generics⟩(⟨?&⟩self, ⟨args⟩) -> ⟨name⟩::Output ⟨generics⟩ ⟨?clone_bounds⟩ {
 //   let ⟨field1⟩ = self.⟨field1⟩;
 //   …
 //   let ⟨fieldN⟩ = self.⟨fieldN⟩;
+//   ⟨?{ intermediate_body }⟩
 //   ⟨output_instance⟩
 // }
 //
 // ∨
 //
-// pub ⟨asyncness⟩ fn ⟨name⟩⟨generics⟩(self, ⟨args⟩) -> ⟨return_type⟩ {
+// pub ⟨asyncness⟩ fn ⟨name⟩⟨generics⟩(⟨?&⟩self, ⟨args⟩) -> ⟨return_type⟩ ⟨where_clause⟩ {
 //   let ⟨field1⟩ = self.⟨field1⟩;
 //   …
 //   let ⟨fieldN⟩ = self.⟨fieldN⟩;
-//   ⟨body⟩
+//   ⟨body ∨ trait_dispatch⟩
 // }
+//
+// ∨
+//
+// ⟨asyncness⟩ fn ⟨name⟩⟨generics⟩(⟨args⟩) -> ⟨return_type⟩;
 pub fn produce_method(
     prelude: &Prelude,
     view: &DocumentationBlockView,
@@ -40,32 +59,83 @@ pub fn produce_method(
     let spans = [span];
 
     let depth = chain.depth();
-    let is_deepest = matches!(tail, BranchTail::Leaf { .. });
+    let is_deepest = tail.is_final();
+    // Inside traits, leaves forward their arguments to a flattened trait method.
+    let dispatches = is_deepest && 0 < depth && chain.is_in_trait();
+    let binds_patterns = matches!(tail, BranchTail::Leaf { .. }) && !dispatches;
+    // Continuations taking `&self` clone the fields of the current output.
+    let by_ref = prelude.options.by_ref && !chain.is_last();
+    let clone_bounds = match chain.into_iter().skip(1).find(|c| !c.is_skipped()) {
+        Some(owner) if by_ref => produce_field_bounds(owner, &quote! { ::core::clone::Clone }),
+        _ => Vec::new(),
+    };
 
     view.section_at(depth).to_tokens(tokens);
-    produce_method_prelude(prelude, tokens, depth, is_deepest);
+    produce_method_prelude(prelude, chain, tokens, is_deepest);
 
     syn::token::Fn { span }.to_tokens(tokens);
     output_section.ident.to_tokens(tokens);
     chain.generics().produce_last_contrained_generics(tokens);
     output_section.paren_token.surround(tokens, |tokens| {
         if !chain.is_last() {
+            if by_ref {
+                syn::token::And { spans }.to_tokens(tokens);
+            }
             syn::token::SelfValue { span }.to_tokens(tokens);
             syn::token::Comma { spans }.to_tokens(tokens);
         }
-        output_section.inputs.to_tokens(tokens)
+        for (n, arg) in chain.args().iter().enumerate() {
+            if 0 < n {
+                syn::token::Comma { spans }.to_tokens(tokens);
+            }
+            if binds_patterns {
+                arg.to_tokens(tokens);
+            } else {
+                arg.push_field_arg_to_tokens(tokens);
+            }
+        }
     });
 
     match tail {
-        BranchTail::Alternative { .. } => {
-            syn::token::RArrow {
-                spans: [span, span],
+        BranchTail::Alternative { body, .. } => {
+            let mut next_step = TokenStream::new();
+            produce_output_name_with_namespace(chain, &mut next_step);
+            let wrapper = body
+                .as_ref()
+                .and_then(|body| produce_fallible_output(chain, &body.output, &next_step, tokens));
+            if wrapper.is_none() {
+                syn::token::RArrow {
+                    spans: [span, span],
+                }
+                .to_tokens(tokens);
+                next_step.to_tokens(tokens);
             }
-            .to_tokens(tokens);
-            produce_output_name_with_namespace(chain, tokens);
+            let mut bounds = clone_bounds;
+            if depth == 0 && chain.is_in_trait() {
+                bounds.push(parse_quote! { Self: ::core::marker::Sized });
+            }
+            chain.generics().produce_last_where_clause(bounds, tokens);
             Brace::default().surround(tokens, |tokens| {
-                produce_output_deconstruction(chain, tokens);
-                produce_output_instance(chain, tokens)
+                produce_output_deconstruction(chain, false, by_ref, tokens);
+                if let Some(body) = body {
+                    produce_intermediate_body(chain, body, tokens);
+                }
+                let mut instance = TokenStream::new();
+                produce_output_instance(chain, &mut instance);
+                match wrapper {
+                    Some(wrapper) => quote! { #wrapper(#instance) }.to_tokens(tokens),
+                    None => instance.to_tokens(tokens),
+                }
+            });
+        }
+        BranchTail::Leaf { output, .. } | BranchTail::Declaration { output, .. } if dispatches => {
+            produce_leaf_output(chain, output, tokens);
+            chain
+                .generics()
+                .produce_last_where_clause(clone_bounds, tokens);
+            Brace::default().surround(tokens, |tokens| {
+                produce_output_deconstruction(chain, false, by_ref, tokens);
+                produce_trait_dispatch(prelude, chain, tokens);
             });
         }
         BranchTail::Leaf {
@@ -74,13 +144,221 @@ pub fn produce_method(
             body,
             ..
         } => {
-            output.to_tokens(tokens);
-            chain.generics().produce_where_clause(tokens);
+            produce_leaf_output(chain, output, tokens);
+            chain
+                .generics()
+                .produce_last_where_clause(clone_bounds, tokens);
             brace.surround(tokens, |tokens| {
-                produce_output_deconstruction(chain, tokens);
-                body.to_tokens(tokens);
+                produce_output_deconstruction(chain, true, by_ref, tokens);
+                produce_body_stmts(chain, body).to_tokens(tokens);
             });
         }
+        BranchTail::Declaration { output, semi_token } => {
+            produce_leaf_output(chain, output, tokens);
+            chain.generics().produce_last_where_clause([], tokens);
+            semi_token.to_tokens(tokens);
+        }
+    }
+}
+
+// { ⟨stmts⟩ }
+//
+// ∨
+//
+// let (⟨value1⟩, …, ⟨valueN⟩,) = { ⟨stmts⟩ (⟨value1⟩, …, ⟨valueN⟩,) };
+fn produce_intermediate_body(
+    chain: &BrowsingChain,
+    body: &IntermediateBody,
+    tokens: &mut TokenStream,
+) {
+    let stmts = produce_body_stmts(chain, &body.stmts);
+    if chain.derived().is_empty() {
+        body.brace
+            .surround(tokens, |tokens| stmts.to_tokens(tokens));
+        return;
+    }
+    let mut values = TokenStream::new();
+    for value in chain.derived() {
+        value.push_ident_to_tokens(&mut values);
+        syn::token::Comma::default().to_tokens(&mut values);
+    }
+    quote! { let (#values) = { #stmts (#values) }; }.to_tokens(tokens);
+}
+
+// Statements of a body, where `Self` designates the root type again and `self` the receiver.
+fn produce_body_stmts(chain: &BrowsingChain, stmts: &TokenStream) -> TokenStream {
+    let mut stmts = stmts.clone();
+    if let Some(root_type) = chain.root_type() {
+        stmts = SelfTypeReplacer::new(root_type).replace_in_tokens(stmts);
+    }
+    if chain.stores_receiver() {
+        stmts = ReceiverReplacer::new(&chain.config().receiver).replace_in_tokens(stmts);
+    }
+    stmts
+}
+
+// ⟨return_type⟩ where `Self` designates the root type again
+fn replace_self_type(chain: &BrowsingChain, output: &ReturnType) -> ReturnType {
+    let mut output = output.clone();
+    if let Some(root_type) = chain.root_type() {
+        SelfTypeReplacer::new(root_type).visit_return_type_mut(&mut output);
+    }
+    output
+}
+
+// -> Result<⟨next_step⟩, ⟨error⟩> ∨ -> Option<⟨next_step⟩>
+//
+// Returns the path wrapping the next step, if the section can fail.
+fn produce_fallible_output(
+    chain: &BrowsingChain,
+    output: &ReturnType,
+    next_step: &TokenStream,
+    tokens: &mut TokenStream,
+) -> Option<TokenStream> {
+    let ReturnType::Type(arrow, ty) = replace_self_type(chain, output) else {
+        return None;
+    };
+    let Type::Path(mut path) = *ty else {
+        return None;
+    };
+    let segment = path.path.segments.last_mut()?;
+    let wrapper = if segment.ident == "Result" {
+        quote! { ::core::result::Result::Ok }
+    } else {
+        quote! { ::core::option::Option::Some }
+    };
+    if let PathArguments::AngleBracketed(args) = &mut segment.arguments {
+        if let Some(GenericArgument::Type(ty)) = args.args.first_mut() {
+            *ty = Type::Verbatim(next_step.clone());
+        }
+    }
+    arrow.to_tokens(tokens);
+    path.to_tokens(tokens);
+    Some(wrapper)
+}
+
+// -> ⟨return_type⟩ where elided lifetimes are named
+pub fn produce_leaf_output(chain: &BrowsingChain, output: &ReturnType, tokens: &mut TokenStream) {
+    let output = &replace_self_type(chain, output);
+    match chain.output_lifetime() {
+        Some(lifetime) => ElidedOutputLifetime::replace_in(lifetime, output).to_tokens(tokens),
+        None => output.to_tokens(tokens),
+    }
+}
+
+// fn ⟨name⟩(⟨?mut⟩ self, ⟨args⟩)
+fn produce_repeating_signature(chain: &BrowsingChain, mutable: bool, tokens: &mut TokenStream) {
+    let section = chain.section();
+    let span = Span::call_site();
+    let spans = [span];
+
+    syn::token::Fn { span }.to_tokens(tokens);
+    section.ident.to_tokens(tokens);
+    section.paren_token.surround(tokens, |tokens| {
+        if mutable {
+            syn::token::Mut { span }.to_tokens(tokens);
+        }
+        syn::token::SelfValue { span }.to_tokens(tokens);
+        for arg in chain.args() {
+            syn::token::Comma { spans }.to_tokens(tokens);
+            arg.push_field_arg_to_tokens(tokens);
+        }
+    });
+}
+
+// #[inline]
+// pub fn ⟨name⟩(mut self, ⟨args⟩) -> Self {
+//   self.⟨name⟩.push(⟨element⟩);
+//   self
+// }
+pub fn produce_repeating_method(
+    prelude: &Prelude,
+    view: &DocumentationBlockView,
+    chain: &BrowsingChain,
+    tokens: &mut TokenStream,
+) {
+    let depth = chain.depth();
+    let ident = &chain.section().ident;
+
+    view.section_at(depth).to_tokens(tokens);
+    produce_method_prelude(prelude, chain, tokens, false);
+    let mut signature = TokenStream::new();
+    produce_repeating_signature(chain, true, &mut signature);
+    let mut element = TokenStream::new();
+    produce_repeated_element(chain, &mut element);
+    quote! {
+        #signature -> Self {
+            self.#ident.push(#element);
+            self
+        }
+    }
+    .to_tokens(tokens);
+}
+
+// ⟨?attr⟩ impl ⟨generics⟩ ⟨output⟩ ⟨generics, 1⟩ ⟨where_clause⟩ {
+//   #[inline]
+//   pub fn ⟨name⟩(self, ⟨args⟩) -> ⟨output⟩ ⟨generics, 2⟩ {
+//     let item = ⟨element⟩;
+//     let ⟨output⟩ { ⟨field1⟩, …, ⟨fieldN⟩, .. } = self;
+//     let mut ⟨name⟩ = ⟨name⟩;
+//     ⟨name⟩.push(item);
+//     ⟨output⟩ ⟨generics, 2⟩ { ⟨field1⟩, …, ⟨fieldN⟩, }
+//   }
+// }
+// …
+// ⟨?attr⟩ impl ⟨generics⟩ ⟨output⟩ ⟨generics, max - 1⟩ ⟨where_clause⟩ {
+//   …
+// }
+pub fn produce_bounded_repeating_methods(
+    prelude: &Prelude,
+    view: &DocumentationBlockView,
+    chain: &BrowsingChain,
+    max: usize,
+    tokens: &mut TokenStream,
+) {
+    let depth = chain.depth();
+    let ident = &chain.section().ident;
+    let output = chain.output_ident();
+    let generics = chain.generics();
+
+    let mut signature = TokenStream::new();
+    produce_repeating_signature(chain, false, &mut signature);
+    let mut element = TokenStream::new();
+    produce_repeated_element(chain, &mut element);
+    let mut fields = TokenStream::new();
+    produce_field_idents(chain, &mut fields);
+    let mut phantom = TokenStream::new();
+    produce_phantom_field(chain, false, &mut phantom);
+    // The arguments may be shadowed by the fields, hence an element computed beforehand.
+    let item = Ident::new("item", Span::mixed_site());
+
+    for count in 1..max {
+        let mut impl_generics = TokenStream::new();
+        generics.produce_uncounted_constrained_generics(&mut impl_generics);
+        let mut current_generics = TokenStream::new();
+        generics.produce_counted_generic_names(false, count, &mut current_generics);
+        let mut next_generics = TokenStream::new();
+        generics.produce_counted_generic_names(true, count + 1, &mut next_generics);
+        let mut prelude_tokens = view.section_at(depth).to_token_stream();
+        produce_method_prelude(prelude, chain, &mut prelude_tokens, false);
+        let mut where_clause = TokenStream::new();
+        generics.produce_where_clause(&mut where_clause);
+        let mut item_prelude = TokenStream::new();
+        produce_flat_item_prelude(prelude, chain, &mut item_prelude);
+
+        quote! {
+            #item_prelude
+            impl #impl_generics #output #current_generics #where_clause {
+                #prelude_tokens #signature -> #output #next_generics {
+                    let #item = #element;
+                    let #output { #fields .. } = self;
+                    let mut #ident = #ident;
+                    #ident.push(#item);
+                    #output #next_generics { #fields #phantom }
+                }
+            }
+        }
+        .to_tokens(tokens);
     }
 }
 
@@ -90,6 +368,7 @@ mod tests {
     use crate::flattening::trunk::{flatten_trunk, FlatteningResult};
     use crate::model::attribute::DocumentationBlockView;
     use crate::model::branch::BranchTail;
+    use crate::model::config::GenerationConfig;
     use crate::model::prelude::Prelude;
     use crate::model::trunk::Trunk;
     use proc_macro2::TokenStream;
@@ -98,7 +377,6 @@ mod tests {
     use super::produce_method;
 
     fn collect_method_data(
-        stream: &mut TokenStream,
         method_data: &mut Vec<TokenStream>,
         prelude: &Prelude,
         chain: &BrowsingChain,
@@ -110,9 +388,9 @@ mod tests {
         method_data.push(method);
 
         if let BranchTail::Alternative { rest, .. } = tail {
-            let next_chain = chain.concat(&rest.0.branch.section)?;
+            let next_chain = chain.concat(&rest.0.branch)?;
             let next_tail = &rest.0.branch.tail;
-            collect_method_data(stream, method_data, prelude, &next_chain, next_tail)?
+            collect_method_data(method_data, prelude, &next_chain, next_tail)?
         }
         Ok(())
     }
@@ -122,13 +400,19 @@ mod tests {
         let tokens = quote!(pub(crate) fn first<'a>(text: &'a str, uuid: Uuid).second<T>(n: &'a mut T).third(l: usize) -> i64 { compose(l, uuid, combine(text, n)) });
 
         let trunk = asserts::tokens_are_parsable_as::<Trunk>(tokens);
+        let config = GenerationConfig::default();
 
         let mut stream = TokenStream::new();
         let mut method_data = Vec::<TokenStream>::new();
 
-        flatten_trunk(&mut stream, &trunk, |stream, prelude, _, chain, tail| {
-            collect_method_data(stream, &mut method_data, prelude, chain, tail)
-        })
+        flatten_trunk(
+            &mut stream,
+            &config,
+            &trunk,
+            |_, prelude, _, chain, tail| {
+                collect_method_data(&mut method_data, prelude, chain, tail)
+            },
+        )
         .expect("Should not have failed");
 
         assert_eq!(3, method_data.len());
@@ -1,11 +1,21 @@
+pub mod accessors;
 pub mod chain;
+pub mod derive;
 pub mod doc;
+pub mod impl_traits;
+pub mod lifetimes;
+pub mod merge;
 pub mod method;
 pub mod module;
+pub mod naming;
 pub mod ordered_gens;
 pub mod output;
 pub mod prelude;
+pub mod receiver;
 pub mod root_impl;
+pub mod self_tokens;
+pub mod self_type;
+pub mod trait_def;
 pub mod tree;
 pub mod trunk;
 pub mod usual_args;
@@ -2,15 +2,19 @@ use crate::flattening::trunk::FlatteningResult;
 use crate::model::attribute::DocumentationBlockView;
 use crate::model::branch::{BranchTail, DocumentedBranch};
 use crate::model::prelude::Prelude;
+use crate::model::section::Section;
 use proc_macro2::{Span, TokenStream};
 use quote::{quote, ToTokens};
 use syn::token::Brace;
 
+use super::accessors::produce_accessors;
 use super::chain::BrowsingChain;
+use super::derive::produce_derived_impls;
 use super::doc::produce_linked_doc_for_module;
-use super::method::produce_method;
+use super::method::{produce_bounded_repeating_methods, produce_method, produce_repeating_method};
+use super::naming::FlatTypeNames;
 use super::output::{produce_inherent_impl_header_for_output, produce_output_definition};
-use super::prelude::produce_module_prelude;
+use super::prelude::{produce_flat_item_prelude, produce_module_prelude};
 
 // #![allow(unused_imports)]
 // use super::*;
@@ -25,64 +29,124 @@ fn produce_common_imports(tokens: &mut TokenStream) {
 //
 // pub mod ⟨name⟩
 fn produce_module_header(prelude: &Prelude, chain: &BrowsingChain, tokens: &mut TokenStream) {
-    produce_module_prelude(prelude, tokens, chain.depth());
+    produce_module_prelude(prelude, chain, tokens);
     syn::token::Mod {
         span: Span::call_site(),
     }
     .to_tokens(tokens);
-    chain.section().ident.to_tokens(tokens);
+    chain.module_ident().to_tokens(tokens);
 }
 
-// ⟨common_imports⟩
+type Continuation<'a> = (
+    DocumentationBlockView<'a>,
+    BrowsingChain<'a>,
+    &'a BranchTail,
+);
+
+// Collects the possible continuations of `chain`. Each section that can be skipped adds
+// the continuations that follow it as if it had not been called.
+fn collect_continuations<'a>(
+    chain: &'a BrowsingChain<'a>,
+    skipped: &mut Vec<&'a Section>,
+    view: &DocumentationBlockView<'a>,
+    rest: &'a (DocumentedBranch, Vec<DocumentedBranch>),
+    depth: usize,
+    continuations: &mut Vec<Continuation<'a>>,
+) -> FlatteningResult {
+    let branch_view = rest.0.doc_block.create_view_starting_at(depth);
+    let first_view = if branch_view.is_empty() {
+        *view
+    } else {
+        branch_view
+    };
+    let branches = std::iter::once((first_view, &rest.0)).chain(
+        rest.1
+            .iter()
+            .map(|branch| (branch.doc_block.create_view_starting_at(depth), branch)),
+    );
+
+    for (view, branch) in branches {
+        let section = &branch.branch.section;
+        let next_chain = chain.concat_skipping(skipped.as_slice(), &branch.branch)?;
+        let next_tail = &branch.branch.tail;
+        continuations.push((view, next_chain, next_tail));
+
+        if let (true, BranchTail::Alternative { rest, .. }) = (section.is_skippable(), next_tail) {
+            skipped.push(section);
+            collect_continuations(chain, skipped, &view, rest, depth + 1, continuations)?;
+            skipped.pop();
+        }
+    }
+    Ok(())
+}
+
+// ⟨?common_imports⟩
 // ⟨output_definition⟩
-// ⟨impl_header⟩ {
+// ⟨?derived_impls⟩
+// ⟨?attr⟩ ⟨impl_header⟩ {
+//   ⟨?repeating_method⟩
 //   ⟨method1⟩
 //   …
 //   ⟨methodN⟩
 // }
+// ⟨?bounded_repeating_methods⟩
+// ⟨?accessors⟩
 // ⟨sub_module1⟩
 // …
 // ⟨sub_moduleN⟩
-fn produce_module_body(
+//
+// where the module of a flat layout only holds its items, next to the ones of other sections
+fn produce_module_body<'a>(
     prelude: &Prelude,
-    view: &DocumentationBlockView,
-    rest: &(DocumentedBranch, Vec<DocumentedBranch>),
-    chain: &BrowsingChain,
+    view: &DocumentationBlockView<'a>,
+    rest: &'a (DocumentedBranch, Vec<DocumentedBranch>),
+    chain: &'a BrowsingChain<'a>,
+    flat_names: &mut FlatTypeNames,
     tokens: &mut TokenStream,
 ) -> FlatteningResult {
-    produce_common_imports(tokens);
-    produce_output_definition(chain, tokens);
-
-    let mut continuations = Vec::<(DocumentationBlockView, BrowsingChain, &BranchTail)>::new();
-
-    let depth = chain.depth() + 1;
-
-    let branch_view = rest.0.doc_block.create_view_starting_at(depth);
-    let view = if branch_view.is_empty() {
-        *view
+    if chain.is_flat() {
+        flat_names.register(chain)?;
     } else {
-        branch_view
-    };
-    let first_chain = chain.concat(&rest.0.branch.section)?;
-    let first_tail = &rest.0.branch.tail;
-    continuations.push((view, first_chain, first_tail));
-
-    for branch in &rest.1 {
-        let branch_view = branch.doc_block.create_view_starting_at(depth);
-        let next_chain = chain.concat(&branch.branch.section)?;
-        let next_tail = &branch.branch.tail;
-        continuations.push((branch_view, next_chain, next_tail));
+        produce_common_imports(tokens);
     }
+    produce_output_definition(prelude, chain, tokens);
+    produce_derived_impls(prelude, chain, tokens);
+
+    let mut continuations = Vec::<Continuation>::new();
+    let depth = chain.depth() + 1;
+    collect_continuations(
+        chain,
+        &mut Vec::new(),
+        view,
+        rest,
+        depth,
+        &mut continuations,
+    )?;
 
+    produce_flat_item_prelude(prelude, chain, tokens);
     produce_inherent_impl_header_for_output(chain, tokens);
+    let section = chain.section();
     Brace::default().surround(tokens, |tokens| {
+        if section.is_repeated() && section.max_repetitions().is_none() {
+            produce_repeating_method(prelude, view, chain, tokens);
+        }
         for (view, next_chain, next_tail) in &continuations {
             produce_method(prelude, view, next_chain, next_tail, tokens);
         }
     });
+    if let Some(max) = section.max_repetitions() {
+        produce_bounded_repeating_methods(prelude, view, chain, max, tokens);
+    }
+    let repeating_method = section.is_repeated().then_some(&section.ident);
+    let methods = continuations
+        .iter()
+        .map(|(_, next_chain, _)| &next_chain.section().ident)
+        .chain(repeating_method)
+        .collect::<Vec<_>>();
+    produce_accessors(prelude, chain, &methods, tokens)?;
 
     for (view, next_chain, next_tail) in continuations {
-        produce_module(tokens, prelude, &view, &next_chain, next_tail)?
+        produce_module(tokens, prelude, &view, &next_chain, next_tail, flat_names)?
     }
     Ok(())
 }
@@ -90,19 +154,26 @@ fn produce_module_body(
 // ⟨module_header⟩ {
 //     ⟨module_body⟩
 // }
+//
+// ∨
+//
+// ⟨module_body⟩
 pub fn produce_module(
     tokens: &mut TokenStream,
     prelude: &Prelude,
     view: &DocumentationBlockView,
     chain: &BrowsingChain,
     tail: &BranchTail,
+    flat_names: &mut FlatTypeNames,
 ) -> FlatteningResult {
-    if let BranchTail::Alternative { rest, .. } = tail {
+    if let (true, BranchTail::Alternative { rest, .. }) = (chain.is_flat(), tail) {
+        produce_module_body(prelude, view, rest, chain, flat_names, tokens)
+    } else if let BranchTail::Alternative { rest, .. } = tail {
         produce_linked_doc_for_module(chain, tail, tokens);
         produce_module_header(prelude, chain, tokens);
         let mut result: FlatteningResult = Ok(());
         Brace::default().surround(tokens, |tokens| {
-            result = produce_module_body(prelude, view, rest, chain, tokens);
+            result = produce_module_body(prelude, view, rest, chain, flat_names, tokens);
         });
         result
     } else {
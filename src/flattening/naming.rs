@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+
+use proc_macro2::{Delimiter, Span, TokenStream, TokenTree};
+use quote::{quote_spanned, ToTokens};
+use syn::{ext::IdentExt, Ident};
+
+use crate::model::{options::ChainOptions, section::Section};
+
+use super::chain::{BrowsingChain, RootImplHeader};
+
+const NESTED_TYPE_NAME: &str = "Output";
+
+// released_in ⟶ ReleasedIn
+pub fn to_camel_case(ident: &Ident) -> String {
+    snake_to_camel_case(&ident.unraw().to_string())
+}
+
+fn snake_to_camel_case(name: &str) -> String {
+    let mut camel = String::with_capacity(name.len());
+    for word in name.split('_').filter(|word| !word.is_empty()) {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            camel.extend(first.to_uppercase());
+            camel.push_str(chars.as_str());
+        }
+    }
+    camel
+}
+
+// HTTPClient ⟶ http_client, where runs of capitals form a single word
+fn to_snake_case(name: &str) -> String {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (n, c) in chars.iter().enumerate() {
+        let starts_word = c.is_uppercase()
+            && n.checked_sub(1).is_some_and(|previous| {
+                let previous = chars[previous];
+                let next = chars.get(n + 1);
+                (!previous.is_uppercase() && previous != '_')
+                    || (previous.is_uppercase() && next.is_some_and(|next| next.is_lowercase()))
+            });
+        if starts_word {
+            snake.push('_');
+        }
+        snake.extend(c.to_lowercase());
+    }
+    snake
+}
+
+// The words naming a type in snake case, such as `vec`, `u8` for `Vec<u8>` or `ref`, `slice`,
+// `u8` for `&[u8]`. Lifetimes are left aside.
+fn collect_type_words(tokens: TokenStream, words: &mut Vec<String>) {
+    let mut in_lifetime = false;
+    for tree in tokens {
+        match tree {
+            TokenTree::Ident(ident) => {
+                if !std::mem::take(&mut in_lifetime) {
+                    let word = to_snake_case(&ident.unraw().to_string());
+                    words.push(word.trim_matches('_').to_string());
+                }
+            }
+            TokenTree::Punct(punct) => match punct.as_char() {
+                '&' => words.push("ref".to_string()),
+                '*' => words.push("ptr".to_string()),
+                '\'' => in_lifetime = true,
+                _ => {}
+            },
+            TokenTree::Literal(literal) => {
+                let text = literal.to_string();
+                let word = text.chars().filter(char::is_ascii_alphanumeric);
+                words.push(word.collect::<String>().to_lowercase());
+            }
+            TokenTree::Group(group) => {
+                let is_array = group
+                    .stream()
+                    .into_iter()
+                    .any(|tree| matches!(tree, TokenTree::Punct(p) if p.as_char() == ';'));
+                let kind = match group.delimiter() {
+                    Delimiter::Bracket if is_array => "array",
+                    Delimiter::Bracket => "slice",
+                    Delimiter::Parenthesis if group.stream().is_empty() => "unit",
+                    Delimiter::Parenthesis => "tuple",
+                    _ => "",
+                };
+                if !kind.is_empty() {
+                    words.push(kind.to_string());
+                }
+                collect_type_words(group.stream(), words);
+            }
+        }
+    }
+}
+
+// The implementing type of a trait implementation, in snake case.
+fn implementor_name(root_header: Option<RootImplHeader>) -> Option<String> {
+    let header = root_header.filter(|header| header.implements_trait)?;
+    let mut words = Vec::new();
+    collect_type_words(header.root_type.to_token_stream(), &mut words);
+    words.retain(|word| !word.is_empty());
+    Some(words.join("_"))
+}
+
+// The name of the trait or implementing type that method chains are declared in.
+pub fn root_type_name(root_header: Option<RootImplHeader>) -> Option<String> {
+    match root_header? {
+        RootImplHeader {
+            trait_ident: Some(ident),
+            ..
+        } => Some(ident.to_string()),
+        RootImplHeader {
+            root_type: syn::Type::Path(p),
+            ..
+        } => p
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string()),
+        _ => None,
+    }
+}
+
+// The prefix of flat type names: the whole implementing type of a trait implementation, since
+// the trait can be implemented for other types of the same name, or else the root type name.
+pub fn root_type_prefix(root_header: Option<RootImplHeader>) -> Option<String> {
+    match implementor_name(root_header) {
+        Some(name) => Some(snake_to_camel_case(&name)),
+        None => root_type_name(root_header),
+    }
+}
+
+// The module of the intermediate types of method chains starting with `section_ident`. In a
+// trait implementation, it is prefixed by the whole implementing type, since implementations of
+// the same trait for other types declare the same method chains.
+pub fn root_module_ident(root_header: Option<RootImplHeader>, section_ident: &Ident) -> Ident {
+    match implementor_name(root_header) {
+        Some(name) => {
+            let name = format!("{}_{}", name, section_ident.unraw());
+            Ident::new(&name, section_ident.span())
+        }
+        None => section_ident.clone(),
+    }
+}
+
+// The type returned by the last of `sections`, which are the sections called so far: its explicit
+// name if any, `Output` inside its own module, or else the names of the root type and of all
+// sections in camel case.
+pub fn output_type_ident(
+    options: &ChainOptions,
+    root_name: Option<&str>,
+    sections: &[&Section],
+) -> Ident {
+    let last = sections.last().expect("method chains cannot be empty");
+    if let Some(alias) = &last.alias {
+        return alias.ident.clone();
+    }
+    if !options.is_flat() {
+        return Ident::new(NESTED_TYPE_NAME, Span::call_site());
+    }
+    let camel = root_name
+        .into_iter()
+        .map(str::to_string)
+        .chain(sections.iter().map(|section| to_camel_case(&section.ident)));
+    let camel = camel.collect::<String>();
+    Ident::new(&options.flat_type_name(&camel), Span::call_site())
+}
+
+// Types of the flat layout are all declared in the scope of the macro invocation, where the names
+// derived from different method chains can collide.
+#[derive(Default)]
+pub struct FlatTypeNames {
+    chains: HashMap<String, String>,
+}
+
+impl FlatTypeNames {
+    // Records the type returned by `chain`, unless another method chain already returns a type of
+    // the same name.
+    pub fn register<'a>(&mut self, chain: &'a BrowsingChain<'a>) -> Result<(), TokenStream> {
+        let ident = chain.output_ident();
+        let description = describe_chain(chain);
+        match self.chains.get(&ident.to_string()) {
+            Some(other) if *other != description => {
+                let message = format!(
+                    "method chains `{other}` and `{description}` both produce a type named `{ident}`, rename one of them with `as`"
+                );
+                let span = chain.section().ident.span();
+                Err(quote_spanned! { span => compile_error!(#message); })
+            }
+            _ => {
+                self.chains.insert(ident.to_string(), description);
+                Ok(())
+            }
+        }
+    }
+}
+
+// ⟨?root_type⟩::⟨section1⟩(…).⟨section2⟩(…)…, skipping the sections that were not called
+fn describe_chain<'a>(chain: &'a BrowsingChain<'a>) -> String {
+    let mut sections = chain
+        .into_iter()
+        .filter(|current| !current.is_skipped())
+        .map(|current| format!("{}(…)", current.section().ident))
+        .collect::<Vec<_>>();
+    sections.reverse();
+    let description = sections.join(".");
+    match root_type_name(chain.root_header()) {
+        Some(root_name) => format!("{root_name}::{description}"),
+        None => description,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{output_type_ident, root_module_ident, root_type_prefix, to_snake_case};
+    use crate::flattening::chain::RootImplHeader;
+    use crate::model::{
+        options::{ChainOptions, Layout},
+        section::Section,
+    };
+    use quote::quote;
+
+    #[test]
+    fn name_output_types() {
+        let first =
+            asserts::tokens_are_parsable_as::<Section>(quote!(new_http_request_to(url: Uri)));
+        let second = asserts::tokens_are_parsable_as::<Section>(quote!(r#from(agent: &str)));
+        let named = asserts::tokens_are_parsable_as::<Section>(quote!(with(token: u32) as Token));
+
+        let mut options = ChainOptions::default();
+        let ident = output_type_ident(&options, None, &[&first, &second]);
+        assert_eq!("Output", ident.to_string());
+
+        options.layout = Some(Layout::Flat);
+        let ident = output_type_ident(&options, None, &[&first, &second]);
+        assert_eq!("NewHttpRequestToFrom", ident.to_string());
+
+        let ident = output_type_ident(&options, Some("Client"), &[&first, &second]);
+        assert_eq!("ClientNewHttpRequestToFrom", ident.to_string());
+
+        options.naming = Some("{}State".to_string());
+        let ident = output_type_ident(&options, None, &[&first, &second]);
+        assert_eq!("NewHttpRequestToFromState", ident.to_string());
+
+        let ident = output_type_ident(&options, None, &[&first, &second, &named]);
+        assert_eq!("Token", ident.to_string());
+    }
+
+    #[test]
+    fn name_root_modules() {
+        let section_ident: syn::Ident = syn::parse_quote!(r#describe);
+        let generics = syn::Generics::default();
+        let root_type: syn::Type = syn::parse_quote!(geometry::LineSegment<T>);
+        let mut header = RootImplHeader {
+            generics: &generics,
+            root_type: &root_type,
+            trait_ident: None,
+            implements_trait: false,
+        };
+        let ident = root_module_ident(None, &section_ident);
+        assert_eq!("r#describe", ident.to_string());
+        let ident = root_module_ident(Some(header), &section_ident);
+        assert_eq!("r#describe", ident.to_string());
+
+        header.implements_trait = true;
+        let ident = root_module_ident(Some(header), &section_ident);
+        assert_eq!("geometry_line_segment_t_describe", ident.to_string());
+        assert_eq!(
+            Some("GeometryLineSegmentT".to_string()),
+            root_type_prefix(Some(header))
+        );
+
+        let implementors: [(syn::Type, &str); 5] = [
+            (syn::parse_quote!(Vec<u8>), "vec_u8_describe"),
+            (syn::parse_quote!(&'a mut [u8]), "ref_mut_slice_u8_describe"),
+            (syn::parse_quote!([u8; 4]), "array_u8_4_describe"),
+            (
+                syn::parse_quote!((HTTPClient, ())),
+                "tuple_http_client_unit_describe",
+            ),
+            (syn::parse_quote!(*const r#Self_), "ptr_const_self_describe"),
+        ];
+        for (root_type, expected) in &implementors {
+            header.root_type = root_type;
+            let ident = root_module_ident(Some(header), &section_ident);
+            assert_eq!(*expected, ident.to_string());
+        }
+    }
+
+    #[test]
+    fn convert_to_snake_case() {
+        assert_eq!("point", to_snake_case("Point"));
+        assert_eq!("http_client", to_snake_case("HTTPClient"));
+        assert_eq!("xml_http_request", to_snake_case("XMLHttpRequest"));
+        assert_eq!("line_segment2_d", to_snake_case("LineSegment2D"));
+        assert_eq!("u8", to_snake_case("u8"));
+    }
+}
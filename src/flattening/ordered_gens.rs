@@ -1,7 +1,8 @@
 use proc_macro2::Span;
-use proc_macro2::TokenStream;
+use proc_macro2::{TokenStream, TokenTree};
 use quote::ToTokens;
-use syn::WhereClause;
+use std::collections::HashSet;
+use syn::{parse_quote, WhereClause, WherePredicate};
 use syn::{ConstParam, GenericParam, Generics, LifetimeParam, TypeParam};
 
 pub struct OrderedGenericList {
@@ -9,7 +10,9 @@ pub struct OrderedGenericList {
     const_gens: Vec<ConstParam>,
     type_gens: Vec<TypeParam>,
     last_gens: Vec<GenericParam>,
+    counter: Option<ConstParam>,
     where_clause: Option<WhereClause>,
+    last_where_clause: Option<WhereClause>,
 }
 
 impl OrderedGenericList {
@@ -20,22 +23,23 @@ impl OrderedGenericList {
         let mut lifetime_gens = Vec::<LifetimeParam>::new();
         let mut const_gens = Vec::<ConstParam>::new();
         let mut type_gens = Vec::<TypeParam>::new();
-        let mut where_clause = generics.where_clause.clone();
+        let mut where_clause = None;
         if let Some(list) = previous {
             lifetime_gens = list.lifetime_gens.clone();
             const_gens = list.const_gens.clone();
             type_gens = list.type_gens.clone();
-            if where_clause.is_none() {
-                where_clause = list.where_clause.clone();
-            }
+            where_clause = list.where_clause.clone();
         }
+        where_clause = Self::merge_where_clauses(where_clause, generics.where_clause.as_ref());
         let last_gens = generics.params.iter().cloned().collect();
         let mut list = OrderedGenericList {
             lifetime_gens,
             const_gens,
             type_gens,
             last_gens,
+            counter: None,
             where_clause,
+            last_where_clause: generics.where_clause.clone(),
         };
         for g in generics.params.iter() {
             match g {
@@ -47,12 +51,77 @@ impl OrderedGenericList {
         list
     }
 
+    // Bounds accumulate down the method chain, each predicate being kept once.
+    fn merge_where_clauses(
+        previous: Option<WhereClause>,
+        current: Option<&WhereClause>,
+    ) -> Option<WhereClause> {
+        let Some(current) = current else {
+            return previous;
+        };
+        let Some(mut merged) = previous else {
+            return Some(current.clone());
+        };
+        for predicate in &current.predicates {
+            let text = predicate.to_token_stream().to_string();
+            let is_known = merged
+                .predicates
+                .iter()
+                .any(|known| known.to_token_stream().to_string() == text);
+            if !is_known {
+                merged.predicates.push(predicate.clone());
+            }
+        }
+        Some(merged)
+    }
+
+    pub fn include_last_generics_of(&mut self, previous: &OrderedGenericList) {
+        let mut last_gens = previous.last_gens.clone();
+        last_gens.append(&mut self.last_gens);
+        last_gens.sort_by_key(|g| !matches!(g, GenericParam::Lifetime(_)));
+        self.last_gens = last_gens;
+    }
+
+    // Adds a trailing `const COUNT: usize` parameter, counting the calls of a bounded
+    // repeated section. It is not propagated to the next sections.
+    pub fn with_counter(mut self) -> OrderedGenericList {
+        self.counter = Some(parse_quote! { const COUNT: usize });
+        self
+    }
+
     pub fn count(&self) -> usize {
-        self.lifetime_gens.len() + self.const_gens.len() + self.type_gens.len()
+        self.lifetime_gens.len()
+            + self.const_gens.len()
+            + self.type_gens.len()
+            + usize::from(self.counter.is_some())
     }
 
     // :: <⟨name1⟩, …, ⟨nameN⟩>
     pub fn produce_complete_generic_names(&self, must_prefix: bool, tokens: &mut TokenStream) {
+        let counter = self.counter.as_ref().map(|c| c.ident.to_token_stream());
+        self.produce_generic_names(must_prefix, counter, tokens);
+    }
+
+    // :: <⟨name1⟩, …, ⟨nameN⟩, ⟨count⟩>
+    pub fn produce_counted_generic_names(
+        &self,
+        must_prefix: bool,
+        count: usize,
+        tokens: &mut TokenStream,
+    ) {
+        let counter = self
+            .counter
+            .as_ref()
+            .map(|_| proc_macro2::Literal::usize_unsuffixed(count).to_token_stream());
+        self.produce_generic_names(must_prefix, counter, tokens);
+    }
+
+    fn produce_generic_names(
+        &self,
+        must_prefix: bool,
+        counter: Option<TokenStream>,
+        tokens: &mut TokenStream,
+    ) {
         if self.count() == 0 {
             return;
         }
@@ -75,12 +144,26 @@ impl OrderedGenericList {
         );
         Self::separate_with_comma(self.const_gens.iter().map(|c| &c.ident), &mut first, tokens);
         Self::separate_with_comma(self.type_gens.iter().map(|t| &t.ident), &mut first, tokens);
+        Self::separate_with_comma(counter.iter(), &mut first, tokens);
         syn::token::Gt { spans: [span] }.to_tokens(tokens);
     }
 
     // <⟨generic1⟩, …, ⟨genericN⟩>
     pub fn produce_complete_constrained_generics(&self, tokens: &mut TokenStream) {
-        if self.count() == 0 {
+        self.produce_constrained_generics(true, tokens);
+    }
+
+    // <⟨generic1⟩, …, ⟨genericN⟩> without the counter
+    pub fn produce_uncounted_constrained_generics(&self, tokens: &mut TokenStream) {
+        self.produce_constrained_generics(false, tokens);
+    }
+
+    fn produce_constrained_generics(&self, with_counter: bool, tokens: &mut TokenStream) {
+        let (counter, count) = match &self.counter {
+            Some(_) if !with_counter => (None, self.count() - 1),
+            counter => (counter.as_ref(), self.count()),
+        };
+        if count == 0 {
             return;
         }
 
@@ -91,6 +174,7 @@ impl OrderedGenericList {
         Self::separate_with_comma(self.lifetime_gens.iter(), &mut first, tokens);
         Self::separate_with_comma(self.const_gens.iter(), &mut first, tokens);
         Self::separate_with_comma(self.type_gens.iter(), &mut first, tokens);
+        Self::separate_with_comma(counter.iter(), &mut first, tokens);
         syn::token::Gt { spans: [span] }.to_tokens(tokens);
     }
 
@@ -108,11 +192,83 @@ impl OrderedGenericList {
         syn::token::Gt { spans: [span] }.to_tokens(tokens);
     }
 
+    // Lifetimes and types that `tokens` never mention, which would be rejected as unused
+    // parameters of a type only made of such tokens.
+    pub fn unmentioned_params(&self, tokens: &TokenStream) -> Vec<GenericParam> {
+        let mut mentioned = HashSet::<String>::new();
+        Self::collect_names(tokens.clone(), &mut mentioned);
+        let lifetimes = self
+            .lifetime_gens
+            .iter()
+            .filter(|lt| !mentioned.contains(&lt.lifetime.to_string()))
+            .map(|lt| GenericParam::Lifetime(lt.clone()));
+        let types = self
+            .type_gens
+            .iter()
+            .filter(|tp| !mentioned.contains(&tp.ident.to_string()))
+            .map(|tp| GenericParam::Type(tp.clone()));
+        lifetimes.chain(types).collect()
+    }
+
+    fn collect_names(tokens: TokenStream, names: &mut HashSet<String>) {
+        let mut trees = tokens.into_iter().peekable();
+        while let Some(tree) = trees.next() {
+            match tree {
+                TokenTree::Punct(punct) if punct.as_char() == '\'' => {
+                    if let Some(TokenTree::Ident(ident)) = trees.next() {
+                        names.insert(format!("'{ident}"));
+                    }
+                }
+                TokenTree::Ident(ident) => {
+                    names.insert(ident.to_string());
+                }
+                TokenTree::Group(group) => Self::collect_names(group.stream(), names),
+                _ => {}
+            }
+        }
+    }
+
     // where ⟨constraints⟩
     pub fn produce_where_clause(&self, tokens: &mut TokenStream) {
         self.where_clause.to_tokens(tokens)
     }
 
+    // where ⟨constraints⟩, ⟨predicate1⟩, …, ⟨predicateN⟩
+    pub fn produce_extended_where_clause(
+        &self,
+        predicates: impl IntoIterator<Item = WherePredicate>,
+        tokens: &mut TokenStream,
+    ) {
+        Self::produce_where_clause_with(&self.where_clause, predicates, tokens);
+    }
+
+    // where ⟨last_constraints⟩, ⟨predicate1⟩, …, ⟨predicateN⟩
+    //
+    // Methods only declare the bounds of their own section, as the previous ones are declared by
+    // the implementation holding them.
+    pub fn produce_last_where_clause(
+        &self,
+        predicates: impl IntoIterator<Item = WherePredicate>,
+        tokens: &mut TokenStream,
+    ) {
+        Self::produce_where_clause_with(&self.last_where_clause, predicates, tokens);
+    }
+
+    fn produce_where_clause_with(
+        where_clause: &Option<WhereClause>,
+        predicates: impl IntoIterator<Item = WherePredicate>,
+        tokens: &mut TokenStream,
+    ) {
+        let mut where_clause = where_clause.clone().unwrap_or_else(|| WhereClause {
+            where_token: Default::default(),
+            predicates: Default::default(),
+        });
+        where_clause.predicates.extend(predicates);
+        if !where_clause.predicates.is_empty() {
+            where_clause.to_tokens(tokens)
+        }
+    }
+
     fn separate_with_comma<'a, T: 'a + ToTokens>(
         iterator: impl Iterator<Item = &'a T>,
         first: &mut bool,
@@ -1,67 +1,272 @@
 use proc_macro2::{Span, TokenStream};
-use quote::ToTokens;
+use quote::{quote, ToTokens};
 use syn::{
+    parse_quote,
     token::{Brace, Paren},
-    Ident,
+    GenericParam, WherePredicate,
 };
 
-use super::{chain::BrowsingChain, doc::produce_linked_doc_for_output};
+use crate::model::prelude::Prelude;
 
-// pub struct Output ⟨generics⟩ ⟨where_clause⟩ {
+use super::{
+    chain::BrowsingChain, doc::produce_linked_doc_for_output, prelude::produce_flat_item_prelude,
+    usual_args::UsualArg,
+};
+
+// ⟨ty⟩
+//
+// ∨
+//
+// ::core::option::Option<⟨ty⟩>
+pub fn produce_field_type(
+    chain: &BrowsingChain,
+    current: &BrowsingChain,
+    arg: &UsualArg,
+    tokens: &mut TokenStream,
+) {
+    let root_type = chain.root_header().map(|h| h.root_type);
+    if current.section().is_optional() {
+        let mut ty = TokenStream::new();
+        arg.push_type_to_tokens(root_type, &mut ty);
+        quote! { ::core::option::Option<#ty> }.to_tokens(tokens);
+    } else {
+        arg.push_type_to_tokens(root_type, tokens);
+    }
+}
+
+// ⟨ty⟩ ∨ (⟨ty1⟩, …, ⟨tyN⟩)
+//
+// ∨
+//
+// ::std::vec::Vec<⟨ty⟩ ∨ (⟨ty1⟩, …, ⟨tyN⟩)>
+pub fn produce_repeated_field_type(
+    chain: &BrowsingChain,
+    current: &BrowsingChain,
+    tokens: &mut TokenStream,
+) {
+    let root_type = chain.root_header().map(|h| h.root_type);
+    let mut element = TokenStream::new();
+    match current.args().as_slice() {
+        [arg] => arg.push_type_to_tokens(root_type, &mut element),
+        args => Paren::default().surround(&mut element, |tokens| {
+            for arg in args {
+                arg.push_type_to_tokens(root_type, tokens);
+                syn::token::Comma::default().to_tokens(tokens);
+            }
+        }),
+    }
+    quote! { ::std::vec::Vec<#element> }.to_tokens(tokens);
+}
+
+// ⟨arg⟩ ∨ (⟨arg1⟩, …, ⟨argN⟩)
+pub fn produce_repeated_element(chain: &BrowsingChain, tokens: &mut TokenStream) {
+    match chain.args().as_slice() {
+        [arg] => arg.push_ident_to_tokens(tokens),
+        args => Paren::default().surround(tokens, |tokens| {
+            for arg in args {
+                arg.push_ident_to_tokens(tokens);
+                syn::token::Comma::default().to_tokens(tokens);
+            }
+        }),
+    }
+}
+
+// ⟨field1⟩, …, ⟨fieldN⟩ where a repeated section contributes a single field named after it
+pub fn produce_field_idents(chain: &BrowsingChain, tokens: &mut TokenStream) {
+    let span = Span::call_site();
+    for current in chain {
+        if current.section().is_repeated() {
+            current.section().ident.to_tokens(tokens);
+            syn::token::Comma { spans: [span] }.to_tokens(tokens);
+        } else {
+            for arg in current.fields() {
+                arg.push_ident_to_tokens(tokens);
+                syn::token::Comma { spans: [span] }.to_tokens(tokens);
+            }
+        }
+    }
+}
+
+// for<'__field> ⟨ty1⟩: ⟨trait⟩, …, for<'__field> ⟨tyN⟩: ⟨trait⟩
+//
+// Bounds are declared through higher-ranked predicates so that they are checked at use site,
+// even when field types do not depend on generic parameters.
+pub fn produce_field_bounds(
+    chain: &BrowsingChain,
+    trait_path: &TokenStream,
+) -> Vec<WherePredicate> {
+    output_fields(chain)
+        .into_iter()
+        .map(|(_, ty)| parse_quote! { for<'__field> #ty: #trait_path })
+        .collect()
+}
+
+pub fn has_phantom_field(chain: &BrowsingChain) -> bool {
+    let in_trait = chain
+        .root_header()
+        .is_some_and(|header| header.trait_ident.is_some());
+    in_trait || !unused_generics(chain).is_empty()
+}
+
+// Lifetimes and types that no field stores, such as those only appearing in the output of a leaf.
+fn unused_generics(chain: &BrowsingChain) -> Vec<GenericParam> {
+    let field_types = output_fields(chain)
+        .into_iter()
+        .map(|(_, ty)| ty)
+        .collect::<TokenStream>();
+    chain.generics().unmentioned_params(&field_types)
+}
+
+// Intermediate types must mention their lifetimes and types even when they store none of them.
+// Inside a trait definition, they must refer to the implementing type even when they do not store
+// any receiver.
+//
+// ⟨?pub (super)⟩ _self: ::core::marker::PhantomData<fn() -> (⟨param1⟩, …, ⟨paramN⟩,)>,
+//
+// ∨
+//
+// _self: ::core::marker::PhantomData,
+pub fn produce_phantom_field(chain: &BrowsingChain, is_definition: bool, tokens: &mut TokenStream) {
+    if !has_phantom_field(chain) {
+        return;
+    }
+    // The trait parameters are all mentioned, as `Self_` is bound by the trait.
+    let mut params = match chain.root_header() {
+        Some(header) if header.trait_ident.is_some() => {
+            header.generics.params.iter().cloned().collect()
+        }
+        _ => Vec::new(),
+    };
+    params.extend(unused_generics(chain));
+    let mut marked = Vec::<TokenStream>::new();
+    for param in params {
+        let marker = match param {
+            GenericParam::Type(param) => param.ident.to_token_stream(),
+            GenericParam::Lifetime(param) => {
+                let lifetime = &param.lifetime;
+                quote! { &#lifetime () }
+            }
+            GenericParam::Const(_) => continue,
+        };
+        if !marked.iter().any(|m| m.to_string() == marker.to_string()) {
+            marked.push(marker);
+        }
+    }
+    if is_definition {
+        let field_vis = produce_field_visibility(chain);
+        quote! { #field_vis _self: ::core::marker::PhantomData<fn() -> (#(#marked,)*)>, }
+    } else {
+        quote! { _self: ::core::marker::PhantomData, }
+    }
+    .to_tokens(tokens);
+}
+
+// The fields stored by the output of `chain`, as pairs of identifiers and types, where a repeated
+// section contributes a single field named after it.
+pub fn output_fields(chain: &BrowsingChain) -> Vec<(TokenStream, TokenStream)> {
+    chain
+        .into_iter()
+        .flat_map(|current| section_fields(chain, current))
+        .collect()
+}
+
+// The fields stored for `current`, a section of `chain`.
+pub fn section_fields(
+    chain: &BrowsingChain,
+    current: &BrowsingChain,
+) -> Vec<(TokenStream, TokenStream)> {
+    if current.section().is_repeated() {
+        let ident = current.section().ident.to_token_stream();
+        let mut ty = TokenStream::new();
+        produce_repeated_field_type(chain, current, &mut ty);
+        return vec![(ident, ty)];
+    }
+    let mut fields = Vec::new();
+    for arg in current.fields() {
+        let mut ident = TokenStream::new();
+        arg.push_ident_to_tokens(&mut ident);
+        let mut ty = TokenStream::new();
+        produce_field_type(chain, current, arg, &mut ty);
+        fields.push((ident, ty));
+    }
+    fields
+}
+
+// pub struct ⟨name⟩ ⟨generics⟩ ⟨where_clause⟩ {
 //      pub (super) ⟨field1⟩: ⟨ty1⟩,
 //      …
 //      pub (super) ⟨fieldN⟩: ⟨tyN⟩,
 // }
-pub fn produce_output_definition(chain: &BrowsingChain, tokens: &mut TokenStream) {
+//
+// ∨
+//
+// ⟨attr⟩ ⟨visibility⟩ struct ⟨name⟩ ⟨generics⟩ ⟨where_clause⟩ {
+//      ⟨field1⟩: ⟨ty1⟩,
+//      …
+//      ⟨fieldN⟩: ⟨tyN⟩,
+// }
+pub fn produce_output_definition(
+    prelude: &Prelude,
+    chain: &BrowsingChain,
+    tokens: &mut TokenStream,
+) {
     let span = Span::call_site();
 
     produce_linked_doc_for_output(chain, tokens);
-    syn::token::Pub { span }.to_tokens(tokens);
+    if chain.is_flat() {
+        produce_flat_item_prelude(prelude, chain, tokens);
+        prelude.vis.to_tokens(tokens);
+    } else {
+        syn::token::Pub { span }.to_tokens(tokens);
+    }
     syn::token::Struct { span }.to_tokens(tokens);
-    Ident::new("Output", span).to_tokens(tokens);
+    chain.output_ident().to_tokens(tokens);
     chain
         .generics()
         .produce_complete_constrained_generics(tokens);
     chain.generics().produce_where_clause(tokens);
+    let field_vis = produce_field_visibility(chain);
     Brace::default().surround(tokens, |tokens| {
-        for current in chain {
-            for arg in current.args() {
-                let span = Span::call_site();
-                syn::token::Pub { span }.to_tokens(tokens);
-                Paren::default().surround(tokens, |tokens| {
-                    syn::token::Super { span }.to_tokens(tokens);
-                });
-                arg.push_ident_to_tokens(tokens);
-                syn::token::Colon { spans: [span] }.to_tokens(tokens);
-                arg.push_type_to_tokens(chain.root_header().map(|h| h.root_type), tokens);
-                syn::token::Comma { spans: [span] }.to_tokens(tokens);
-            }
+        for (ident, ty) in output_fields(chain) {
+            quote! { #field_vis #ident: #ty, }.to_tokens(tokens);
         }
+        produce_phantom_field(chain, true, tokens);
     });
 }
 
-// ⟨path⟩ :: Output :: ⟨generics⟩
+// pub(super) ∨ nothing when intermediate types are declared side by side
+fn produce_field_visibility(chain: &BrowsingChain) -> TokenStream {
+    if chain.is_flat() {
+        TokenStream::new()
+    } else {
+        quote! { pub(super) }
+    }
+}
+
+// ⟨path⟩ :: ⟨name⟩ :: ⟨generics⟩ ∨ ⟨name⟩ :: ⟨generics⟩
 pub fn produce_output_name_with_namespace(chain: &BrowsingChain, tokens: &mut TokenStream) {
     let span = Span::call_site();
-    chain.section().ident.to_tokens(tokens);
-    syn::token::PathSep {
-        spans: [span, span],
+    if !chain.is_flat() {
+        chain.module_ident().to_tokens(tokens);
+        syn::token::PathSep {
+            spans: [span, span],
+        }
+        .to_tokens(tokens);
     }
-    .to_tokens(tokens);
-    Ident::new("Output", span).to_tokens(tokens);
+    chain.output_ident().to_tokens(tokens);
     chain
         .generics()
-        .produce_complete_generic_names(true, tokens);
+        .produce_counted_generic_names(true, 1, tokens);
 }
 
-// impl ⟨generics⟩ Output ⟨generics⟩ ⟨where_clause⟩
+// impl ⟨generics⟩ ⟨name⟩ ⟨generics⟩ ⟨where_clause⟩
 pub fn produce_inherent_impl_header_for_output(chain: &BrowsingChain, tokens: &mut TokenStream) {
     let span = Span::call_site();
     syn::token::Impl { span }.to_tokens(tokens);
     chain
         .generics()
         .produce_complete_constrained_generics(tokens);
-    Ident::new("Output", span).to_tokens(tokens);
+    chain.output_ident().to_tokens(tokens);
     chain
         .generics()
         .produce_complete_generic_names(false, tokens);
@@ -78,7 +283,12 @@ pub fn produce_output_instance(chain: &BrowsingChain, tokens: &mut TokenStream)
 
     Brace::default().surround(tokens, |tokens| {
         for current in chain {
-            for arg in current.args() {
+            if current.section().is_repeated() {
+                current.section().ident.to_tokens(tokens);
+                syn::token::Comma { spans }.to_tokens(tokens);
+                continue;
+            }
+            for arg in current.fields() {
                 arg.push_ident_to_tokens(tokens);
                 if depth == 0 && arg.is_receiver() {
                     syn::token::Colon { spans: [span] }.to_tokens(tokens);
@@ -87,27 +297,108 @@ pub fn produce_output_instance(chain: &BrowsingChain, tokens: &mut TokenStream)
                 syn::token::Comma { spans }.to_tokens(tokens)
             }
         }
+        produce_phantom_field(chain, false, tokens);
     })
 }
 
-// let ⟨field1⟩ = self.⟨field1⟩;
+// let ⟨arg1⟩ = ::core::option::Option::Some(⟨arg1⟩);
+// …
+// ∨
+// let ⟨name⟩ = ::std::vec![⟨element⟩];
+//
+// let ⟨field1⟩ = ⟨self.⟨field1⟩ ∨ ::core::clone::Clone::clone(&self.⟨field1⟩)⟩;
+// …
+// let ⟨fieldN⟩ = ⟨self.⟨fieldN⟩ ∨ ::core::clone::Clone::clone(&self.⟨fieldN⟩)⟩;
+// let ⟨skipped1⟩: ⟨ty1⟩ = ⟨::core::option::Option::None ∨ default1⟩;
 // …
-// let ⟨fieldN⟩ = self.⟨fieldN⟩;
-pub fn produce_output_deconstruction(chain: &BrowsingChain, tokens: &mut TokenStream) {
+//
+// where fields and skipped arguments are replaced by their patterns right before the leaf body,
+// and fields available to an intermediate body are prefixed by `#[allow(unused_variables)]` there
+pub fn produce_output_deconstruction(
+    chain: &BrowsingChain,
+    is_deepest: bool,
+    by_ref: bool,
+    tokens: &mut TokenStream,
+) {
     let span = Span::call_site();
     let spans = [span];
+    let push_binding_to_tokens = |arg: &UsualArg, tokens: &mut TokenStream| match arg.pattern() {
+        Some(pat) if is_deepest => pat.to_tokens(tokens),
+        _ => arg.push_ident_to_tokens(tokens),
+    };
 
-    for current in chain.into_iter().skip(1) {
-        for arg in current.args() {
+    if chain.section().is_optional() {
+        for arg in chain.args() {
+            let mut ident = TokenStream::new();
+            arg.push_ident_to_tokens(&mut ident);
+            quote! { let #ident = ::core::option::Option::Some(#ident); }.to_tokens(tokens);
+        }
+    } else if chain.section().is_repeated() {
+        let ident = &chain.section().ident;
+        let mut element = TokenStream::new();
+        produce_repeated_element(chain, &mut element);
+        quote! { let #ident = ::std::vec![#element]; }.to_tokens(tokens);
+    }
+
+    // Sections skipped just before the current one are not stored in `self`.
+    let mut previous = chain.into_iter().skip(1).peekable();
+    let mut skipped = Vec::<&BrowsingChain>::new();
+    while let Some(current) = previous.next_if(|c| c.is_skipped()) {
+        skipped.push(current);
+    }
+
+    let read_field = |ident: &TokenStream| match by_ref {
+        true => quote! { ::core::clone::Clone::clone(&self.#ident) },
+        false => quote! { self.#ident },
+    };
+    // Values that an intermediate body may already have used are not necessarily used by the
+    // final method.
+    let mut is_consumed = false;
+    for current in previous {
+        is_consumed |= current.has_body();
+        let allow_unused = match is_deepest && is_consumed {
+            true => quote! { #[allow(unused_variables)] },
+            false => TokenStream::new(),
+        };
+        if current.section().is_repeated() {
+            let ident = current.section().ident.to_token_stream();
+            let value = read_field(&ident);
+            quote! { #allow_unused let #ident = #value; }.to_tokens(tokens);
+            continue;
+        }
+        for arg in current.fields() {
+            allow_unused.to_tokens(tokens);
             syn::token::Let { span }.to_tokens(tokens);
-            arg.push_ident_to_tokens(tokens);
+            push_binding_to_tokens(arg, tokens);
             syn::token::Eq { spans }.to_tokens(tokens);
-            syn::token::SelfValue { span }.to_tokens(tokens);
-            syn::token::Dot { spans }.to_tokens(tokens);
-            arg.push_ident_to_tokens(tokens);
+            let mut ident = TokenStream::new();
+            arg.push_ident_to_tokens(&mut ident);
+            read_field(&ident).to_tokens(tokens);
             syn::token::Semi { spans }.to_tokens(tokens);
         }
     }
+
+    // Default values may refer to arguments of previous sections.
+    for current in skipped.into_iter().rev() {
+        if current.section().is_repeated() {
+            let ident = &current.section().ident;
+            let mut ty = TokenStream::new();
+            produce_repeated_field_type(chain, current, &mut ty);
+            quote! { let #ident: #ty = ::std::vec::Vec::new(); }.to_tokens(tokens);
+            continue;
+        }
+        for arg in current.args() {
+            let mut ident = TokenStream::new();
+            push_binding_to_tokens(arg, &mut ident);
+            let mut ty = TokenStream::new();
+            produce_field_type(chain, current, arg, &mut ty);
+            match arg.default() {
+                Some(default) => quote! { let #ident: #ty = #default; },
+                None => quote! { let #ident: #ty = ::core::option::Option::None; },
+            }
+            .to_tokens(tokens);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -115,6 +406,7 @@ mod tests {
     use crate::flattening::chain::BrowsingChain;
     use crate::flattening::trunk::{flatten_trunk, FlatteningResult};
     use crate::model::branch::BranchTail;
+    use crate::model::config::GenerationConfig;
     use crate::model::prelude::Prelude;
     use crate::model::trunk::Trunk;
     use proc_macro2::TokenStream;
@@ -125,14 +417,13 @@ mod tests {
     };
 
     fn collect_output_data(
-        stream: &mut TokenStream,
         output_data: &mut Vec<TokenStream>,
         prelude: &Prelude,
         chain: &BrowsingChain,
         tail: &BranchTail,
     ) -> FlatteningResult {
         let mut output_definition = TokenStream::new();
-        produce_output_definition(chain, &mut output_definition);
+        produce_output_definition(prelude, chain, &mut output_definition);
         output_data.push(output_definition);
 
         let mut output_instance = TokenStream::new();
@@ -144,9 +435,9 @@ mod tests {
         output_data.push(output_instance);
 
         if let BranchTail::Alternative { rest, .. } = tail {
-            let next_chain = chain.concat(&rest.0.branch.section)?;
+            let next_chain = chain.concat(&rest.0.branch)?;
             let next_tail = &rest.0.branch.tail;
-            collect_output_data(stream, output_data, prelude, &next_chain, next_tail)?
+            collect_output_data(output_data, prelude, &next_chain, next_tail)?
         }
         Ok(())
     }
@@ -156,13 +447,19 @@ mod tests {
         let tokens = quote!(pub(crate) fn first<'a>(text: &'a str).second<T>(n: &'a mut T) {});
 
         let trunk = asserts::tokens_are_parsable_as::<Trunk>(tokens);
+        let config = GenerationConfig::default();
 
         let mut stream = TokenStream::new();
         let mut output_data = Vec::<TokenStream>::new();
 
-        flatten_trunk(&mut stream, &trunk, |stream, prelude, _, chain, tail| {
-            collect_output_data(stream, &mut output_data, prelude, chain, tail)
-        })
+        flatten_trunk(
+            &mut stream,
+            &config,
+            &trunk,
+            |_, prelude, _, chain, tail| {
+                collect_output_data(&mut output_data, prelude, chain, tail)
+            },
+        )
         .expect("Should not have failed");
 
         assert_eq!(6, output_data.len());
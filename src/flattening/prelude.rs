@@ -1,18 +1,31 @@
 use proc_macro2::{Span, TokenStream};
 
-use crate::model::prelude::Prelude;
+use crate::model::{
+    config::{GenerationConfig, Inlining},
+    prelude::Prelude,
+    section::Section,
+};
 use quote::{quote, ToTokens};
+use syn::{Token, Visibility};
 
-// ⟨attr⟩ ⟨visibility⟩
+use super::chain::BrowsingChain;
+
+// ⟨attr⟩ ⟨configured_visibility ∨ visibility⟩
 //
 // ∨
 //
-// pub
-pub fn produce_module_prelude(prelude: &Prelude, tokens: &mut TokenStream, depth: usize) {
-    if depth == 0 {
+// ⟨cfg⟩ pub
+pub fn produce_module_prelude(prelude: &Prelude, chain: &BrowsingChain, tokens: &mut TokenStream) {
+    if chain.depth() == 0 {
         prelude.attr_block.to_tokens(tokens);
-        prelude.vis.to_tokens(tokens);
+        match &chain.config().module_visibility {
+            Some(vis) => vis.to_tokens(tokens),
+            None => prelude.vis.to_tokens(tokens),
+        }
     } else {
+        for cfg in chain.section().attr_block.cfgs() {
+            cfg.to_tokens(tokens);
+        }
         syn::token::Pub {
             span: Span::call_site(),
         }
@@ -20,32 +33,84 @@ pub fn produce_module_prelude(prelude: &Prelude, tokens: &mut TokenStream, depth
     }
 }
 
-// ⟨?#[inline]⟩ ⟨attr⟩ ⟨visibility⟩ ⟨?async⟩
+// ⟨attr⟩ ⟨cfg1⟩ … ⟨cfgN⟩
+//
+// Without modules to carry them, the attributes of a method chain and the `cfg` attributes of
+// its sections are repeated on each of its intermediate items.
+pub fn produce_flat_item_prelude(
+    prelude: &Prelude,
+    chain: &BrowsingChain,
+    tokens: &mut TokenStream,
+) {
+    if chain.is_flat() {
+        prelude.attr_block.to_tokens(tokens);
+        produce_chain_cfgs(chain, tokens);
+    }
+}
+
+// ⟨cfg1⟩ … ⟨cfgN⟩ for all sections called so far
+pub fn produce_chain_cfgs(chain: &BrowsingChain, tokens: &mut TokenStream) {
+    for current in chain.into_iter().filter(|current| !current.is_skipped()) {
+        for cfg in current.section().attr_block.cfgs() {
+            cfg.to_tokens(tokens);
+        }
+    }
+}
+
+// #[inline] ∨ #[inline(always)] ∨ #[inline(never)] ∨ nothing
+pub fn produce_inline_attribute(config: &GenerationConfig, tokens: &mut TokenStream) {
+    match config.inline {
+        Inlining::Hint => quote! { #[inline] }.to_tokens(tokens),
+        Inlining::Always => quote! { #[inline(always)] }.to_tokens(tokens),
+        Inlining::Never => quote! { #[inline(never)] }.to_tokens(tokens),
+        Inlining::Off => {}
+    }
+}
+
+// An `async fn` makes the last sections of its method chains async, while any other section
+// can be made async individually.
+pub fn section_asyncness<'a>(
+    prelude: &'a Prelude,
+    section: &'a Section,
+    is_deepest: bool,
+) -> Option<&'a Token![async]> {
+    match (&section.asyncness, &prelude.asyncness) {
+        (Some(async_token), _) => Some(async_token),
+        (None, Some(async_token)) if is_deepest => Some(async_token),
+        _ => None,
+    }
+}
+
+// ⟨?inline⟩ ⟨attr⟩ ⟨visibility⟩ ⟨?async⟩ ⟨?unsafe⟩
 //
 // ∨
 //
-// ⟨?#[inline]⟩ pub ⟨?async⟩
+// ⟨?inline⟩ ⟨section_attr⟩ ⟨section_visibility ∨ pub⟩ ⟨?async⟩ ⟨?unsafe⟩
 pub fn produce_method_prelude(
     prelude: &Prelude,
+    chain: &BrowsingChain,
     tokens: &mut TokenStream,
-    depth: usize,
     is_deepest: bool,
 ) {
+    let section = chain.section();
     if !is_deepest {
-        quote! { #[inline] }.to_tokens(tokens);
+        produce_inline_attribute(chain.config(), tokens);
     }
 
-    if depth == 0 {
+    if chain.depth() == 0 {
         prelude.attr_block.to_tokens(tokens);
         prelude.vis.to_tokens(tokens);
     } else {
-        syn::token::Pub {
-            span: Span::call_site(),
+        section.attr_block.to_tokens(tokens);
+        match &section.vis {
+            Visibility::Inherited => syn::token::Pub {
+                span: Span::call_site(),
+            }
+            .to_tokens(tokens),
+            vis => vis.to_tokens(tokens),
         }
-        .to_tokens(tokens);
     }
 
-    if is_deepest {
-        prelude.asyncness.to_tokens(tokens);
-    }
+    section_asyncness(prelude, section, is_deepest).to_tokens(tokens);
+    section.unsafety.to_tokens(tokens);
 }
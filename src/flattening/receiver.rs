@@ -0,0 +1,128 @@
+use proc_macro2::{Ident, Literal, TokenStream};
+use quote::ToTokens;
+use syn::LitStr;
+
+use super::self_tokens::SelfTokenReplacer;
+
+// Replaces `self` by the stored receiver, since `self` designates the intermediate type inside the
+// methods of the generated modules.
+pub struct ReceiverReplacer<'a> {
+    receiver: &'a Ident,
+}
+
+impl<'a> ReceiverReplacer<'a> {
+    pub fn new(receiver: &'a Ident) -> Self {
+        Self { receiver }
+    }
+}
+
+impl SelfTokenReplacer for ReceiverReplacer<'_> {
+    fn keyword(&self) -> &str {
+        "self"
+    }
+
+    // `self::⟨path⟩` still designates the current module.
+    fn produce_replacement(&self, keyword: &Ident, is_prefix: bool, tokens: &mut TokenStream) {
+        match is_prefix {
+            true => keyword.to_tokens(tokens),
+            false => Ident::new(&self.receiver.to_string(), keyword.span()).to_tokens(tokens),
+        }
+    }
+
+    fn replace_in_macro_literal(&self, literal: Literal) -> Literal {
+        let Ok(format) = syn::parse2::<LitStr>(literal.to_token_stream()) else {
+            return literal;
+        };
+        match rename_format_captures(&format.value(), &self.receiver.to_string()) {
+            Some(renamed) => LitStr::new(&renamed, literal.span()).token(),
+            None => literal,
+        }
+    }
+}
+
+// Renames the `{self}` and `{self:…}` arguments captured by a format string, if any.
+fn rename_format_captures(format: &str, name: &str) -> Option<String> {
+    let mut renamed = String::with_capacity(format.len());
+    let mut rest = format;
+    let mut is_renamed = false;
+    while let Some(start) = rest.find('{') {
+        renamed.push_str(&rest[..=start]);
+        rest = &rest[start + 1..];
+        if let Some(escaped) = rest.strip_prefix('{') {
+            renamed.push('{');
+            rest = escaped;
+        } else if let Some(after) = rest.strip_prefix("self") {
+            if after.starts_with('}') || after.starts_with(':') {
+                renamed.push_str(name);
+                rest = after;
+                is_renamed = true;
+            }
+        }
+    }
+    renamed.push_str(rest);
+    is_renamed.then_some(renamed)
+}
+
+#[cfg(test)]
+mod tests {
+    use proc_macro2::{Ident, Span};
+    use quote::quote;
+
+    use super::{rename_format_captures, ReceiverReplacer};
+    use crate::flattening::self_tokens::SelfTokenReplacer;
+
+    #[test]
+    fn replace_receivers() {
+        let receiver = Ident::new("self_", Span::call_site());
+        let replacer = ReceiverReplacer::new(&receiver);
+
+        let body = quote! {
+            let total = self.items.iter().map(|item| self.weight(item)).sum::<u32>();
+            use std::io::{self, Write};
+            impl Helper { fn get(&self) -> u8 { self.0 } }
+            self::log(format!("{self:?} {{self}} {}", self.len()));
+            total
+        };
+        asserts::equivalent!(
+            replacer.replace_in_tokens(body).to_string().as_str(),
+            "let total = self_ . items . iter () . map (| item | self_ . weight (item)) . sum :: < u32 > () ;
+            use std :: io :: { self , Write } ;
+            impl Helper { fn get (& self) -> u8 { self . 0 } }
+            self :: log (format ! (\"{self_:?} {{self}} {}\" , self_ . len ())) ;
+            total"
+        );
+    }
+
+    #[test]
+    fn replace_receivers_in_method_calls_named_after_items() {
+        let receiver = Ident::new("self_", Span::call_site());
+        let replacer = ReceiverReplacer::new(&receiver);
+
+        let body = quote! {
+            let both = self.a.union(&self.b).count();
+            let union = self.c.iter().collect::<Vec<_>>();
+            #[allow(dead_code)]
+            pub(crate) union Bits { value: u8, flag: bool }
+            impl Bits { fn get(&self) -> u8 { unsafe { self.value } } }
+            self.d.impl_count(union.len())
+        };
+        asserts::equivalent!(
+            replacer.replace_in_tokens(body).to_string().as_str(),
+            "let both = self_ . a . union (& self_ . b) . count () ;
+            let union = self_ . c . iter () . collect :: < Vec < _ >> () ;
+            # [allow (dead_code)]
+            pub (crate) union Bits { value : u8 , flag : bool }
+            impl Bits { fn get (& self) -> u8 { unsafe { self . value } } }
+            self_ . d . impl_count (union . len ())"
+        );
+    }
+
+    #[test]
+    fn rename_captured_receivers() {
+        assert_eq!(
+            Some("{this} {this:>8} {{self}} {self_}".to_string()),
+            rename_format_captures("{self} {self:>8} {{self}} {self_}", "this")
+        );
+        assert_eq!(None, rename_format_captures("{} {0} {selfish}", "this"));
+    }
+}
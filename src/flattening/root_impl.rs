@@ -4,7 +4,7 @@ use quote::ToTokens;
 
 use crate::model::trunk::ImplHeader;
 
-// impl⟨generics⟩ ⟨self_type⟩ {
+// impl⟨generics⟩ ⟨?trait for⟩ ⟨self_type⟩ {
 //   ⟨impl_body⟩
 // }
 pub fn produce_root_impl(
@@ -14,6 +14,10 @@ pub fn produce_root_impl(
 ) {
     header.impl_token.to_tokens(tokens);
     header.generics.to_tokens(tokens);
+    if let Some((path, for_token)) = &header.trait_ {
+        path.to_tokens(tokens);
+        for_token.to_tokens(tokens);
+    }
     header.self_ty.to_tokens(tokens);
     header.generics.where_clause.to_tokens(tokens);
     header
@@ -0,0 +1,123 @@
+use proc_macro2::{Delimiter, Group, Ident, Literal, TokenStream, TokenTree};
+use quote::ToTokens;
+
+// Keywords introducing items whose body declares its own `Self` and `self`.
+const SELF_DECLARING_ITEMS: [&str; 6] = ["impl", "trait", "struct", "enum", "union", "use"];
+
+// Replaces a keyword such as `Self` or `self` in the statements of a body, except inside nested
+// items that declare their own.
+pub trait SelfTokenReplacer {
+    fn keyword(&self) -> &str;
+
+    // Replacement of the keyword, knowing whether it starts a path.
+    fn produce_replacement(&self, keyword: &Ident, is_prefix: bool, tokens: &mut TokenStream);
+
+    // Replacement of a literal passed to a macro invocation, such as a format string.
+    fn replace_in_macro_literal(&self, literal: Literal) -> Literal {
+        literal
+    }
+
+    fn replace_in_tokens(&self, tokens: TokenStream) -> TokenStream {
+        replace_in_stream(self, tokens, false)
+    }
+}
+
+fn mentions_keyword(tokens: &TokenStream, keyword: &str) -> bool {
+    tokens.clone().into_iter().any(|tree| match tree {
+        TokenTree::Ident(ident) => ident == keyword,
+        TokenTree::Group(group) => mentions_keyword(&group.stream(), keyword),
+        TokenTree::Literal(literal) => literal.to_string().contains(keyword),
+        TokenTree::Punct(_) => false,
+    })
+}
+
+// Whether the tokens at `index` start a nested item, which must begin a statement. A `union` item
+// is told apart from a variable or method of the same name by its name and body.
+fn starts_item(trees: &[TokenTree], index: usize, at_statement_start: bool) -> bool {
+    let TokenTree::Ident(ident) = &trees[index] else {
+        return false;
+    };
+    if !at_statement_start || !SELF_DECLARING_ITEMS.iter().any(|keyword| ident == keyword) {
+        return false;
+    }
+    if ident != "union" {
+        return true;
+    }
+    matches!(trees.get(index + 1), Some(TokenTree::Ident(_)))
+        && trees[index + 2..]
+            .iter()
+            .take_while(|tree| !matches!(tree, TokenTree::Punct(p) if p.as_char() == ';'))
+            .any(|tree| matches!(tree, TokenTree::Group(g) if g.delimiter() == Delimiter::Brace))
+}
+
+// Attributes, visibilities and `unsafe` can precede an item at the start of a statement.
+fn keeps_statement_start(trees: &[TokenTree], index: usize) -> bool {
+    match &trees[index] {
+        TokenTree::Punct(punct) => punct.as_char() == '#',
+        TokenTree::Ident(ident) => ident == "pub" || ident == "unsafe",
+        TokenTree::Group(group) => match (group.delimiter(), index.checked_sub(1)) {
+            (Delimiter::Bracket, Some(previous)) => {
+                matches!(&trees[previous], TokenTree::Punct(p) if p.as_char() == '#')
+            }
+            (Delimiter::Parenthesis, Some(previous)) => {
+                matches!(&trees[previous], TokenTree::Ident(i) if i == "pub")
+            }
+            _ => false,
+        },
+        TokenTree::Literal(_) => false,
+    }
+}
+
+fn replace_in_stream<R: SelfTokenReplacer + ?Sized>(
+    replacer: &R,
+    tokens: TokenStream,
+    in_macro: bool,
+) -> TokenStream {
+    if !mentions_keyword(&tokens, replacer.keyword()) {
+        return tokens;
+    }
+    let trees = tokens.into_iter().collect::<Vec<_>>();
+    let mut replaced = TokenStream::new();
+    let mut in_item = false;
+    let mut at_statement_start = true;
+    for (index, tree) in trees.iter().enumerate() {
+        // Whether the previous tokens are `⟨ident⟩!`, so that this group holds macro arguments.
+        let after_macro_name = index >= 2
+            && matches!(&trees[index - 1], TokenTree::Punct(p) if p.as_char() == '!')
+            && matches!(&trees[index - 2], TokenTree::Ident(_));
+        in_item |= starts_item(&trees, index, at_statement_start);
+        at_statement_start = match tree {
+            TokenTree::Punct(punct) if punct.as_char() == ';' => true,
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => true,
+            _ => at_statement_start && keeps_statement_start(&trees, index),
+        };
+        match tree {
+            TokenTree::Ident(ident) if ident == replacer.keyword() && !in_item => {
+                let is_prefix = matches!(trees.get(index + 1), Some(TokenTree::Punct(punct)) if punct.as_char() == ':');
+                replacer.produce_replacement(ident, is_prefix, &mut replaced);
+            }
+            TokenTree::Group(group) if in_item => {
+                // The item ends with its body.
+                in_item = group.delimiter() != Delimiter::Brace;
+                group.to_tokens(&mut replaced);
+            }
+            TokenTree::Group(group) => {
+                let stream = replace_in_stream(replacer, group.stream(), after_macro_name);
+                let mut restored = Group::new(group.delimiter(), stream);
+                restored.set_span(group.span());
+                restored.to_tokens(&mut replaced);
+            }
+            TokenTree::Literal(literal) if in_macro && !in_item => {
+                replacer
+                    .replace_in_macro_literal(literal.clone())
+                    .to_tokens(&mut replaced);
+            }
+            TokenTree::Punct(punct) => {
+                in_item &= punct.as_char() != ';';
+                punct.to_tokens(&mut replaced);
+            }
+            tree => tree.to_tokens(&mut replaced),
+        }
+    }
+    replaced
+}
@@ -0,0 +1,127 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::{quote, ToTokens};
+use syn::visit_mut::VisitMut;
+use syn::{PathArguments, QSelf, Type};
+
+use super::self_tokens::SelfTokenReplacer;
+
+// Replaces `Self` by the type of the root implementation, which `Self` no longer designates
+// inside the generated modules.
+pub struct SelfTypeReplacer<'a> {
+    root_type: &'a Type,
+}
+
+impl<'a> SelfTypeReplacer<'a> {
+    pub fn new(root_type: &'a Type) -> Self {
+        Self { root_type }
+    }
+
+    // ⟨path⟩::<⟨args⟩> ∨ <⟨type⟩> ∨ ⟨type⟩
+    //
+    // In expressions and patterns, generic arguments require a turbofish and other types must be
+    // qualified before an associated item.
+    fn produce_expr_type(&self, is_prefix: bool, tokens: &mut TokenStream) {
+        match self.root_type {
+            Type::Path(path) if path.qself.is_none() => {
+                let mut path = path.path.clone();
+                for segment in &mut path.segments {
+                    if let PathArguments::AngleBracketed(args) = &mut segment.arguments {
+                        args.colon2_token = Some(Default::default());
+                    }
+                }
+                path.to_tokens(tokens);
+            }
+            root_type if is_prefix => quote! { <#root_type> }.to_tokens(tokens),
+            root_type => root_type.to_tokens(tokens),
+        }
+    }
+}
+
+// Same as `visit_type_mut` on a body made of statements.
+impl SelfTokenReplacer for SelfTypeReplacer<'_> {
+    fn keyword(&self) -> &str {
+        "Self"
+    }
+
+    fn produce_replacement(&self, _keyword: &Ident, is_prefix: bool, tokens: &mut TokenStream) {
+        self.produce_expr_type(is_prefix, tokens);
+    }
+}
+
+impl VisitMut for SelfTypeReplacer<'_> {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        match ty {
+            Type::Path(path) if path.qself.is_none() && path.path.is_ident("Self") => {
+                *ty = self.root_type.clone();
+            }
+            // `Self::Assoc` becomes `<⟨type⟩>::Assoc`.
+            Type::Path(path)
+                if path.qself.is_none()
+                    && path.path.leading_colon.is_none()
+                    && path
+                        .path
+                        .segments
+                        .first()
+                        .is_some_and(|s| s.ident == "Self") =>
+            {
+                let segments = std::mem::take(&mut path.path.segments);
+                path.path.segments = segments.into_iter().skip(1).collect();
+                path.qself = Some(QSelf {
+                    lt_token: Default::default(),
+                    ty: Box::new(self.root_type.clone()),
+                    position: 0,
+                    as_token: None,
+                    gt_token: Default::default(),
+                });
+                path.path.leading_colon = Some(Default::default());
+                syn::visit_mut::visit_type_mut(self, ty);
+            }
+            _ => syn::visit_mut::visit_type_mut(self, ty),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::{quote, ToTokens};
+    use syn::visit_mut::VisitMut;
+    use syn::Type;
+
+    use super::SelfTypeReplacer;
+    use crate::flattening::self_tokens::SelfTokenReplacer;
+
+    #[test]
+    fn replace_self_types() {
+        let root_type: Type = syn::parse_quote!(Grid<T, 3>);
+        let mut replacer = SelfTypeReplacer::new(&root_type);
+
+        let mut ty: Type = syn::parse_quote!(Result<Vec<Self>, Self::Error>);
+        replacer.visit_type_mut(&mut ty);
+        assert_eq!(
+            ty.to_token_stream().to_string(),
+            "Result < Vec < Grid < T , 3 > > , < Grid < T , 3 > > :: Error >"
+        );
+
+        let body = quote! {
+            let grid = Self { cells: Self::cells() };
+            struct Cell { next: Option<Box<Self>> }
+            impl Cell { fn new() -> Self { todo!() } }
+            grid.merge(Self::default())
+        };
+        asserts::equivalent!(
+            replacer.replace_in_tokens(body).to_string().as_str(),
+            "let grid = Grid :: < T , 3 > { cells : Grid :: < T , 3 > :: cells () } ;
+            struct Cell { next : Option < Box < Self >> }
+            impl Cell { fn new () -> Self { todo ! () } }
+            grid . merge (Grid :: < T , 3 > :: default ())"
+        );
+
+        let root_type: Type = syn::parse_quote!([u8]);
+        let replacer = SelfTypeReplacer::new(&root_type);
+        let body = quote! { Self::len(bytes) };
+        asserts::equivalent!(
+            replacer.replace_in_tokens(body).to_string().as_str(),
+            "< [u8] > :: len (bytes)"
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::{GenericParam, Ident, WherePredicate};
+
+use crate::model::{branch::BranchTail, prelude::Prelude, trunk::TraitHeader};
+
+use super::{
+    chain::BrowsingChain,
+    method::produce_leaf_output,
+    output::{produce_field_type, produce_repeated_field_type},
+    prelude::{produce_chain_cfgs, section_asyncness},
+    receiver::ReceiverReplacer,
+    self_tokens::SelfTokenReplacer,
+    trunk::FlatteningResult,
+};
+
+// Type parameter standing for `Self` in the intermediate types of a trait.
+pub const SELF_TYPE_PARAM: &str = "Self_";
+
+// Outside of the generated modules, the type parameter is `Self` again.
+pub fn restore_self_type(tokens: TokenStream) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|tree| match tree {
+            TokenTree::Ident(ident) if ident == SELF_TYPE_PARAM => {
+                TokenTree::Ident(Ident::new("Self", ident.span()))
+            }
+            TokenTree::Group(group) => {
+                let stream = restore_self_type(group.stream());
+                let mut restored = Group::new(group.delimiter(), stream);
+                restored.set_span(group.span());
+                TokenTree::Group(restored)
+            }
+            tree => tree,
+        })
+        .collect()
+}
+
+// ⟨section1⟩_…_⟨sectionN⟩
+fn produce_flattened_name(chain: &BrowsingChain) -> Ident {
+    let mut names = chain
+        .into_iter()
+        .map(|current| current.section().ident.to_string())
+        .collect::<Vec<_>>();
+    names.reverse();
+    Ident::new(names.join("_").as_str(), Span::call_site())
+}
+
+// Self_::⟨flattened_name⟩(⟨arg1⟩, …, ⟨argN⟩)⟨?.await⟩
+//
+// ∨
+//
+// unsafe { Self_::⟨flattened_name⟩(⟨arg1⟩, …, ⟨argN⟩)⟨?.await⟩ }
+pub fn produce_trait_dispatch(prelude: &Prelude, chain: &BrowsingChain, tokens: &mut TokenStream) {
+    let name = produce_flattened_name(chain);
+    let Some(header) = chain.root_header() else {
+        return;
+    };
+    let root_type = header.root_type;
+    let mut nodes = chain.into_iter().collect::<Vec<_>>();
+    nodes.reverse();
+    let mut args = TokenStream::new();
+    for current in nodes {
+        if current.section().is_repeated() {
+            current.section().ident.to_tokens(&mut args);
+            syn::token::Comma::default().to_tokens(&mut args);
+            continue;
+        }
+        for arg in current.fields() {
+            arg.push_ident_to_tokens(&mut args);
+            syn::token::Comma::default().to_tokens(&mut args);
+        }
+    }
+    let mut call = quote! { #root_type::#name(#args) };
+    if section_asyncness(prelude, chain.section(), true).is_some() {
+        quote! { .await }.to_tokens(&mut call);
+    }
+    match chain.section().unsafety {
+        Some(unsafe_token) => quote! { #unsafe_token { #call } }.to_tokens(tokens),
+        None => call.to_tokens(tokens),
+    }
+}
+
+// #[doc = ⟨description⟩]
+// ⟨cfg1⟩ … ⟨cfgN⟩
+// ⟨asyncness⟩ ⟨?unsafe⟩ fn ⟨flattened_name⟩⟨generics⟩(⟨args⟩) -> ⟨return_type⟩ ⟨where_clause⟩;
+//
+// ∨
+//
+// #[doc = ⟨description⟩]
+// ⟨cfg1⟩ … ⟨cfgN⟩
+// ⟨asyncness⟩ ⟨?unsafe⟩ fn ⟨flattened_name⟩⟨generics⟩(⟨args⟩) -> ⟨return_type⟩ ⟨where_clause⟩ {
+//   let ⟨receiver⟩ = self;
+//   ⟨body⟩
+// }
+fn produce_trait_leaf_method(
+    prelude: &Prelude,
+    chain: &BrowsingChain,
+    tail: &BranchTail,
+    tokens: &mut TokenStream,
+) {
+    let name = produce_flattened_name(chain);
+    let is_provided = matches!(tail, BranchTail::Leaf { .. });
+    let mut nodes = chain.into_iter().collect::<Vec<_>>();
+    nodes.reverse();
+    let receiver = nodes[0].args().first().filter(|arg| arg.is_receiver());
+
+    let description = nodes
+        .iter()
+        .map(|current| format!("{}(…)", current.section().ident))
+        .collect::<Vec<_>>()
+        .join(".");
+    let description = format!("Implementation of the method chain `{description}`.");
+    quote! { #[doc = #description] }.to_tokens(tokens);
+    produce_chain_cfgs(chain, tokens);
+    section_asyncness(prelude, chain.section(), true).to_tokens(tokens);
+    chain.section().unsafety.to_tokens(tokens);
+    syn::token::Fn::default().to_tokens(tokens);
+    name.to_tokens(tokens);
+
+    let mut lifetimes = Vec::<&GenericParam>::new();
+    let mut others = Vec::<&GenericParam>::new();
+    let mut predicates = Vec::<&WherePredicate>::new();
+    for current in &nodes {
+        let generics = current.own_generics();
+        for param in &generics.params {
+            match param {
+                GenericParam::Lifetime(_) => lifetimes.push(param),
+                _ => others.push(param),
+            }
+        }
+        if let Some(where_clause) = &generics.where_clause {
+            predicates.extend(where_clause.predicates.iter());
+        }
+    }
+    if !lifetimes.is_empty() || !others.is_empty() {
+        quote! { <#(#lifetimes,)* #(#others,)*> }.to_tokens(tokens);
+    }
+
+    syn::token::Paren::default().surround(tokens, |tokens| {
+        if let Some(receiver) = receiver {
+            receiver.to_tokens(tokens);
+            syn::token::Comma::default().to_tokens(tokens);
+        }
+        for current in &nodes {
+            if current.section().is_repeated() {
+                current.section().ident.to_tokens(tokens);
+                syn::token::Colon::default().to_tokens(tokens);
+                produce_repeated_field_type(chain, current, tokens);
+                syn::token::Comma::default().to_tokens(tokens);
+                continue;
+            }
+            for arg in current.fields().filter(|arg| !arg.is_receiver()) {
+                match arg.pattern() {
+                    Some(pat) if is_provided => pat.to_tokens(tokens),
+                    _ => arg.push_ident_to_tokens(tokens),
+                }
+                syn::token::Colon::default().to_tokens(tokens);
+                produce_field_type(chain, current, arg, tokens);
+                syn::token::Comma::default().to_tokens(tokens);
+            }
+        }
+    });
+
+    match tail {
+        BranchTail::Leaf { output, body, .. } => {
+            produce_leaf_output(chain, output, tokens);
+            if !predicates.is_empty() {
+                quote! { where #(#predicates,)* }.to_tokens(tokens);
+            }
+            let receiver_ident = &chain.config().receiver;
+            let (receiver_binding, body) = match receiver {
+                Some(_) => (
+                    Some(quote! { let #receiver_ident = self; }),
+                    ReceiverReplacer::new(receiver_ident).replace_in_tokens(body.clone()),
+                ),
+                None => (None, body.clone()),
+            };
+            quote! { { #receiver_binding #body } }.to_tokens(tokens);
+        }
+        BranchTail::Declaration { output, semi_token } => {
+            produce_leaf_output(chain, output, tokens);
+            if !predicates.is_empty() {
+                quote! { where #(#predicates,)* }.to_tokens(tokens);
+            }
+            semi_token.to_tokens(tokens);
+        }
+        BranchTail::Alternative { .. } => {}
+    }
+}
+
+// Produces the flattened trait method of each leaf reachable from `chain`.
+pub fn produce_trait_leaf_methods(
+    prelude: &Prelude,
+    chain: &BrowsingChain,
+    tail: &BranchTail,
+    tokens: &mut TokenStream,
+) -> FlatteningResult {
+    match tail {
+        BranchTail::Alternative { rest, .. } => {
+            for branch in std::iter::once(&rest.0).chain(rest.1.iter()) {
+                let next_chain = chain.concat(&branch.branch)?;
+                produce_trait_leaf_methods(prelude, &next_chain, &branch.branch.tail, tokens)?;
+            }
+        }
+        _ if chain.depth() == 0 => {}
+        _ => {
+            let mut method_tokens = TokenStream::new();
+            produce_trait_leaf_method(prelude, chain, tail, &mut method_tokens);
+            tokens.extend(restore_self_type(method_tokens));
+        }
+    }
+    Ok(())
+}
+
+// ⟨prelude⟩ trait ⟨name⟩⟨generics⟩ ⟨?: supertraits⟩ ⟨where_clause⟩ {
+//   ⟨trait_body⟩
+// }
+pub fn produce_trait(
+    prelude: &Prelude,
+    header: &TraitHeader,
+    trait_body_tokens: &TokenStream,
+    tokens: &mut TokenStream,
+) {
+    prelude.to_tokens(tokens);
+    header.trait_token.to_tokens(tokens);
+    header.ident.to_tokens(tokens);
+    header.generics.to_tokens(tokens);
+    if let Some((colon_token, bounds)) = &header.supertraits {
+        colon_token.to_tokens(tokens);
+        bounds.to_tokens(tokens);
+    }
+    header.generics.where_clause.to_tokens(tokens);
+    header
+        .brace_token
+        .surround(tokens, |tokens| trait_body_tokens.to_tokens(tokens));
+}
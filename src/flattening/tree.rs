@@ -1,13 +1,37 @@
+use std::collections::HashSet;
+
 use crate::flattening::trunk::flatten_trunk;
 use crate::model::tree::Tree;
 use proc_macro2::TokenStream;
 
+use super::merge::merge_shared_prefixes;
 use super::module::produce_module;
+use super::naming::FlatTypeNames;
 
 pub fn flatten(tree: Tree) -> TokenStream {
+    let tree = match merge_shared_prefixes(tree) {
+        Ok(tree) => tree,
+        Err(error) => return error,
+    };
     let mut tokens = TokenStream::new();
+    // Merged method chains share their module, which is only produced once.
+    let mut produced = HashSet::<String>::new();
+    let mut flat_names = FlatTypeNames::default();
     for trunk in tree.roots {
-        if let Err(error) = flatten_trunk(&mut tokens, &trunk, produce_module) {
+        let config = &tree.config;
+        let result = flatten_trunk(
+            &mut tokens,
+            config,
+            &trunk,
+            |tokens, prelude, view, chain, tail| {
+                if produced.insert(chain.module_ident().to_string()) {
+                    produce_module(tokens, prelude, view, chain, tail, &mut flat_names)
+                } else {
+                    Ok(())
+                }
+            },
+        );
+        if let Err(error) = result {
             return error;
         }
     }
@@ -79,4 +103,64 @@ mod tests {
             }"
         );
     }
+
+    #[test]
+    fn test_flatten_conflicting_accessors() {
+        let tokens = quote!(#[assemblist(accessors)] fn first(second: usize).second() {});
+
+        let tree = asserts::tokens_are_parsable_as::<Tree>(tokens);
+
+        let output = flatten(tree);
+
+        asserts::equivalent!(
+            output.to_string().as_str(),
+            "compile_error ! (\"cannot generate an accessor for `second`, as a method of the same name continues the method chain\") ;"
+        );
+    }
+
+    #[test]
+    fn test_flatten_conflicting_flat_names() {
+        let tokens = quote!(
+            #![assemblist(layout = "flat")]
+            fn a_b(x: u8).c().z() {}
+            fn a(x: u8).b_c().z() {}
+        );
+
+        let tree = asserts::tokens_are_parsable_as::<Tree>(tokens);
+
+        let output = flatten(tree);
+
+        asserts::equivalent!(
+            output.to_string().as_str(),
+            "compile_error ! (\"method chains `a_b(…).c(…)` and `a(…).b_c(…)` both produce a type named `ABC`, rename one of them with `as`\") ;"
+        );
+    }
+
+    #[test]
+    fn test_flatten_ambiguous_output_lifetime() {
+        let tokens = quote!(fn two(a: &str).b(b: &str) -> &str { a });
+
+        let tree = asserts::tokens_are_parsable_as::<Tree>(tokens);
+
+        let output = flatten(tree);
+
+        asserts::equivalent!(
+            output.to_string().as_str(),
+            "compile_error ! (\"missing lifetime specifier: the return type borrows from one of several arguments of the method chain, so its lifetime must be named\") ;"
+        );
+    }
+
+    #[test]
+    fn test_flatten_uninitialized_derived_value() {
+        let tokens = quote!(fn first().second() { let url: String; }.third() {});
+
+        let tree = asserts::tokens_are_parsable_as::<Tree>(tokens);
+
+        let output = flatten(tree);
+
+        asserts::equivalent!(
+            output.to_string().as_str(),
+            "compile_error ! (\"derived value `url` must be initialized where it is declared\") ;"
+        );
+    }
 }
@@ -1,17 +1,25 @@
-use super::{chain::BrowsingChain, method::produce_method, root_impl::produce_root_impl};
+use super::{
+    chain::BrowsingChain,
+    method::produce_method,
+    root_impl::produce_root_impl,
+    trait_def::{produce_trait, produce_trait_leaf_methods, restore_self_type, SELF_TYPE_PARAM},
+};
 use crate::model::{
     attribute::DocumentationBlockView,
     branch::BranchTail,
+    config::GenerationConfig,
     prelude::Prelude,
     trunk::{Trunk, TrunkAlternative},
 };
-use proc_macro2::TokenStream;
+use proc_macro2::{Span, TokenStream};
 use quote::ToTokens;
+use syn::{parse_quote, Ident, Type};
 
 pub type FlatteningResult = std::result::Result<(), TokenStream>;
 
 pub fn flatten_trunk(
     tokens: &mut TokenStream,
+    config: &GenerationConfig,
     trunk: &Trunk,
     mut yield_module: impl FnMut(
         &mut TokenStream,
@@ -25,19 +33,29 @@ pub fn flatten_trunk(
         TrunkAlternative::Fn { documented, .. } => {
             let branch = &documented.branch;
             let view = documented.doc_block.create_view_starting_at(0);
-            let chain = BrowsingChain::new(&branch.section)?;
+            let chain = BrowsingChain::new(config, &trunk.prelude.options, branch)?;
             produce_method(&trunk.prelude, &view, &chain, &branch.tail, tokens);
             yield_module(tokens, &trunk.prelude, &view, &chain, &branch.tail)
         }
-        TrunkAlternative::Impl { header, fn_trunks } => {
+        TrunkAlternative::Impl {
+            header,
+            items,
+            fn_trunks,
+        } => {
             let mut impl_body_tokens = TokenStream::new();
+            for item in items {
+                item.to_tokens(&mut impl_body_tokens);
+            }
             for fn_trunk in fn_trunks {
                 let branch = &fn_trunk.documented.branch;
                 let view = fn_trunk.documented.doc_block.create_view_starting_at(0);
                 let chain = BrowsingChain::new_root_impl(
+                    config,
+                    &fn_trunk.prelude.options,
                     &header.generics,
                     &header.self_ty,
-                    &branch.section,
+                    header.trait_.is_some(),
+                    branch,
                 )?;
                 produce_method(
                     &fn_trunk.prelude,
@@ -52,6 +70,60 @@ pub fn flatten_trunk(
             produce_root_impl(header, &impl_body_tokens, tokens);
             Ok(())
         }
+        TrunkAlternative::Trait {
+            header,
+            items,
+            fn_trunks,
+        } => {
+            let trait_ident = &header.ident;
+            let self_param = Ident::new(SELF_TYPE_PARAM, Span::call_site());
+            let (_, trait_generics, _) = header.generics.split_for_impl();
+            let mut generics = header.generics.clone();
+            generics
+                .params
+                .push(parse_quote! { #self_param: #trait_ident #trait_generics });
+            let self_ty: Type = parse_quote! { #self_param };
+
+            let mut trait_body_tokens = TokenStream::new();
+            for item in items {
+                item.to_tokens(&mut trait_body_tokens);
+            }
+            for fn_trunk in fn_trunks {
+                let branch = &fn_trunk.documented.branch;
+                let view = fn_trunk.documented.doc_block.create_view_starting_at(0);
+                let chain = BrowsingChain::new_root_trait(
+                    config,
+                    &fn_trunk.prelude.options,
+                    &generics,
+                    &self_ty,
+                    trait_ident,
+                    branch,
+                )?;
+                let mut method_tokens = TokenStream::new();
+                produce_method(
+                    &fn_trunk.prelude,
+                    &view,
+                    &chain,
+                    &branch.tail,
+                    &mut method_tokens,
+                );
+                trait_body_tokens.extend(restore_self_type(method_tokens));
+                produce_trait_leaf_methods(
+                    &fn_trunk.prelude,
+                    &chain,
+                    &branch.tail,
+                    &mut trait_body_tokens,
+                )?;
+                // Intermediate types are as visible as the trait itself.
+                let module_prelude = Prelude {
+                    vis: trunk.prelude.vis.clone(),
+                    ..fn_trunk.prelude.clone()
+                };
+                yield_module(tokens, &module_prelude, &view, &chain, &branch.tail)?;
+            }
+            produce_trait(&trunk.prelude, header, &trait_body_tokens, tokens);
+            Ok(())
+        }
     }
 }
 
@@ -60,6 +132,7 @@ mod tests {
     use crate::flattening::chain::BrowsingChain;
     use crate::flattening::trunk::{flatten_trunk, FlatteningResult};
     use crate::model::branch::BranchTail;
+    use crate::model::config::GenerationConfig;
     use crate::model::prelude::Prelude;
     use crate::model::trunk::Trunk;
 
@@ -67,7 +140,6 @@ mod tests {
     use quote::quote;
 
     fn analyse_branch(
-        stream: &mut TokenStream,
         calls: &mut usize,
         prelude: &Prelude,
         chain: &BrowsingChain,
@@ -80,28 +152,20 @@ mod tests {
                 assert!(chain.previous().is_none());
                 assert_eq!(2, chain.section().generics.params.len());
                 assert_eq!(1, chain.args().len());
-                assert!(if let BranchTail::Leaf { .. } = tail {
-                    false
-                } else {
-                    true
-                });
+                assert!(!matches!(tail, BranchTail::Leaf { .. }));
             }
             1 => {
                 assert!(chain.previous().is_some());
                 assert_eq!(1, chain.section().generics.params.len());
                 assert_eq!(2, chain.args().len());
-                assert!(if let BranchTail::Leaf { .. } = tail {
-                    true
-                } else {
-                    false
-                });
+                assert!(matches!(tail, BranchTail::Leaf { .. }));
             }
             _ => {}
         }
         if let BranchTail::Alternative { rest, .. } = tail {
-            let next_chain = chain.concat(&rest.0.branch.section)?;
+            let next_chain = chain.concat(&rest.0.branch)?;
             let next_tail = &rest.0.branch.tail;
-            analyse_branch(stream, calls, prelude, &next_chain, next_tail)?;
+            analyse_branch(calls, prelude, &next_chain, next_tail)?;
         }
         Ok(())
     }
@@ -111,13 +175,17 @@ mod tests {
         let tokens = quote!(fn first<'a, T>(text: &'a str).second<U>(n: &'a mut i32, ok: bool,) {});
 
         let trunk = asserts::tokens_are_parsable_as::<Trunk>(tokens);
+        let config = GenerationConfig::default();
 
         let mut calls = 0;
         let mut stream = TokenStream::new();
 
-        flatten_trunk(&mut stream, &trunk, |stream, prelude, _, chain, tail| {
-            analyse_branch(stream, &mut calls, prelude, chain, tail)
-        })
+        flatten_trunk(
+            &mut stream,
+            &config,
+            &trunk,
+            |_, prelude, _, chain, tail| analyse_branch(&mut calls, prelude, chain, tail),
+        )
         .expect("Should not have failed");
 
         assert_eq!(2, calls);
@@ -1,26 +1,43 @@
-use proc_macro2::{Span, TokenStream};
+use proc_macro2::TokenStream;
 use quote::{quote_spanned, ToTokens};
 use std::result::Result;
+use syn::ext::IdentExt;
+use syn::parse::Parser;
 use syn::punctuated::Punctuated;
-use syn::token::Comma;
-use syn::{Attribute, FnArg, Ident, Lifetime, Pat, PatType, Receiver, Token, Type};
+use syn::spanned::Spanned;
+use syn::visit_mut::VisitMut;
+use syn::{
+    Attribute, Block, Expr, FnArg, Ident, Lifetime, Local, Pat, PatType, Receiver, Stmt, Token,
+    Type,
+};
+
+use crate::model::{branch::IntermediateBody, section::Section};
+
+use super::impl_traits::ImplTraitDesugarer;
+use super::lifetimes::ElidedLifetimeNamer;
+use super::self_type::SelfTypeReplacer;
 
 enum UsualArgAlternative {
     Identified {
         ident: Ident,
+        pat: Option<Box<Pat>>,
         colon_token: Token![:],
         ty: Box<Type>,
     },
+    // Once stored, the receiver is named after the configured `ident`.
     Receiver {
         reference: Option<(Token![&], Option<Lifetime>)>,
         mutability: Option<Token![mut]>,
         self_token: Token![self],
+        typed: Option<(Token![:], Box<Type>)>,
+        ident: Ident,
     },
 }
 
 pub struct UsualArg {
     attrs: Vec<Attribute>,
     alt: UsualArgAlternative,
+    default: Option<Expr>,
 }
 
 impl UsualArg {
@@ -28,19 +45,98 @@ impl UsualArg {
         matches!(&self.alt, UsualArgAlternative::Receiver { .. })
     }
 
-    pub fn push_ident_to_tokens(&self, tokens: &mut TokenStream) {
+    pub fn default(&self) -> Option<&Expr> {
+        self.default.as_ref()
+    }
+
+    // The pattern the argument was declared with, if it is not a plain identifier.
+    pub fn pattern(&self) -> Option<&Pat> {
+        match &self.alt {
+            UsualArgAlternative::Identified { pat, .. } => pat.as_deref(),
+            UsualArgAlternative::Receiver { .. } => None,
+        }
+    }
+
+    pub fn name_elided_lifetimes(&mut self, namer: &mut ElidedLifetimeNamer) {
+        match &mut self.alt {
+            UsualArgAlternative::Identified { ty, .. } => namer.visit_type_mut(ty),
+            UsualArgAlternative::Receiver {
+                reference, typed, ..
+            } => {
+                if let Some((_, lifetime)) = reference {
+                    namer.name(lifetime);
+                }
+                if let Some((_, ty)) = typed {
+                    namer.visit_type_mut(ty);
+                }
+            }
+        }
+    }
+
+    pub fn replace_self_type(&mut self, replacer: &mut SelfTypeReplacer) {
+        if let UsualArgAlternative::Identified { ty, .. } = &mut self.alt {
+            replacer.visit_type_mut(ty);
+        }
+    }
+
+    pub fn desugar_impl_traits(&mut self, desugarer: &mut ImplTraitDesugarer) {
+        if let UsualArgAlternative::Identified { ty, .. } = &mut self.alt {
+            desugarer.visit_type_mut(ty);
+        }
+    }
+
+    // The lifetime of a `&self` or `&mut self` receiver.
+    pub fn receiver_lifetime(&self) -> Option<&Lifetime> {
         match &self.alt {
-            UsualArgAlternative::Identified { ident, .. } => ident.to_tokens(tokens),
-            UsualArgAlternative::Receiver { .. } => {
-                let span = Span::call_site();
-                Ident::new("self_", span).to_tokens(tokens);
+            UsualArgAlternative::Receiver {
+                reference: Some((_, lifetime)),
+                ..
+            } => lifetime.as_ref(),
+            _ => None,
+        }
+    }
+
+    // Same as `to_tokens` except that patterns are replaced by the name of the field storing them.
+    pub fn push_field_arg_to_tokens(&self, tokens: &mut TokenStream) {
+        match &self.alt {
+            UsualArgAlternative::Identified {
+                ident,
+                colon_token,
+                ty,
+                ..
+            } => {
+                for attr in &self.attrs {
+                    attr.to_tokens(tokens);
+                }
+                ident.to_tokens(tokens);
+                colon_token.to_tokens(tokens);
+                ty.to_tokens(tokens);
             }
+            UsualArgAlternative::Receiver { .. } => self.to_tokens(tokens),
+        }
+    }
+
+    pub fn push_ident_to_tokens(&self, tokens: &mut TokenStream) {
+        match &self.alt {
+            UsualArgAlternative::Identified { ident, .. }
+            | UsualArgAlternative::Receiver { ident, .. } => ident.to_tokens(tokens),
         }
     }
 
     pub fn push_type_to_tokens(&self, root_impl_type: Option<&Type>, tokens: &mut TokenStream) {
         match &self.alt {
             UsualArgAlternative::Identified { ty, .. } => ty.to_tokens(tokens),
+            UsualArgAlternative::Receiver {
+                typed: Some((_, ty)),
+                ..
+            } => match root_impl_type {
+                Some(root_type) => {
+                    let mut ty = ty.as_ref().clone();
+                    SelfTypeReplacer::new(root_type).visit_type_mut(&mut ty);
+                    ty.to_tokens(tokens);
+                }
+                None => ty.to_tokens(tokens),
+            },
             UsualArgAlternative::Receiver {
                 reference,
                 mutability,
@@ -68,8 +164,8 @@ impl UsualArg {
     #[cfg(test)]
     fn name(&self) -> String {
         match &self.alt {
-            UsualArgAlternative::Identified { ident, .. } => ident.to_string(),
-            UsualArgAlternative::Receiver { .. } => "self_".to_string(),
+            UsualArgAlternative::Identified { ident, .. }
+            | UsualArgAlternative::Receiver { ident, .. } => ident.to_string(),
         }
     }
 }
@@ -82,10 +178,14 @@ impl ToTokens for UsualArg {
         match &self.alt {
             UsualArgAlternative::Identified {
                 ident,
+                pat,
                 colon_token,
                 ty,
             } => {
-                ident.to_tokens(tokens);
+                match pat {
+                    Some(pat) => pat.to_tokens(tokens),
+                    None => ident.to_tokens(tokens),
+                }
                 colon_token.to_tokens(tokens);
                 ty.to_tokens(tokens);
             }
@@ -93,6 +193,8 @@ impl ToTokens for UsualArg {
                 reference,
                 mutability,
                 self_token,
+                typed,
+                ..
             } => {
                 if let Some((ampersand, lifetime)) = reference {
                     ampersand.to_tokens(tokens);
@@ -100,6 +202,10 @@ impl ToTokens for UsualArg {
                 }
                 mutability.to_tokens(tokens);
                 self_token.to_tokens(tokens);
+                if let Some((colon_token, ty)) = typed {
+                    colon_token.to_tokens(tokens);
+                    ty.to_tokens(tokens);
+                }
             }
         }
     }
@@ -108,103 +214,152 @@ impl ToTokens for UsualArg {
 pub type UsualArgExtractionResult = Result<Vec<UsualArg>, TokenStream>;
 
 impl UsualArg {
-    pub fn extract_usual_args(args: &Punctuated<FnArg, Comma>) -> UsualArgExtractionResult {
+    pub fn extract_usual_args(section: &Section, receiver: &Ident) -> UsualArgExtractionResult {
         let mut output_args: Vec<Self> = Vec::new();
-        for input in args.iter() {
-            match input {
-                FnArg::Typed(typed_arg) => {
-                    let arg = Self::extract_usual_arg(typed_arg)?;
-                    output_args.push(arg);
-                }
-                FnArg::Receiver(receiver) => {
-                    let arg = Self::extract_receiver(receiver)?;
-                    output_args.push(arg);
-                }
-            }
+        for (n, input) in section.inputs.iter().enumerate() {
+            let mut arg = match &input.fn_arg {
+                FnArg::Typed(typed_arg) => Self::extract_usual_arg(section, n, typed_arg)?,
+                FnArg::Receiver(self_arg) => Self::extract_receiver(self_arg, receiver)?,
+            };
+            arg.default = input.default.as_ref().map(|default| default.expr.clone());
+            output_args.push(arg);
         }
         Ok(output_args)
     }
 
-    fn extract_usual_arg(typed_arg: &PatType) -> Result<UsualArg, TokenStream> {
-        match &*typed_arg.pat {
-            Pat::Ident(pat_ident) => {
-                if let Some(subpat) = &pat_ident.subpat {
-                    let message = "Subpatterns are not supported";
-                    let span = subpat.0.span;
-                    return Err(quote_spanned! { span => compile_error!(#message); });
-                }
-                if let Some(by_ref) = &pat_ident.by_ref {
-                    let message = "By ref parameter are not supported";
-                    let span = by_ref.span;
-                    return Err(quote_spanned! { span => compile_error!(#message); });
-                }
-                if let Some(mutability) = &pat_ident.mutability {
-                    let message = "Mutability is not supported";
-                    let span = mutability.span;
+    // Arguments declared with a pattern are stored in a field with a synthesized name.
+    fn extract_usual_arg(
+        section: &Section,
+        n: usize,
+        typed_arg: &PatType,
+    ) -> Result<UsualArg, TokenStream> {
+        let (ident, pat) = match &*typed_arg.pat {
+            Pat::Ident(pat_ident)
+                if pat_ident.by_ref.is_none()
+                    && pat_ident.mutability.is_none()
+                    && pat_ident.subpat.is_none() =>
+            {
+                (pat_ident.ident.clone(), None)
+            }
+            Pat::Ident(pat_ident) if pat_ident.subpat.is_none() => {
+                (pat_ident.ident.clone(), Some(typed_arg.pat.clone()))
+            }
+            pat => {
+                if section.is_optional() {
+                    let message = "Patterns are not supported in optional sections";
+                    let span = typed_arg.colon_token.span;
                     return Err(quote_spanned! { span => compile_error!(#message); });
                 }
-                Ok(UsualArg {
-                    attrs: typed_arg.attrs.clone(),
-                    alt: UsualArgAlternative::Identified {
-                        ident: pat_ident.ident.clone(),
-                        colon_token: typed_arg.colon_token,
-                        ty: typed_arg.ty.clone(),
-                    },
-                })
+                let name = format!("__{}_{}", section.ident.unraw(), n);
+                let ident = Ident::new(name.as_str(), pat.span());
+                (ident, Some(typed_arg.pat.clone()))
+            }
+        };
+        Ok(UsualArg {
+            attrs: typed_arg.attrs.clone(),
+            alt: UsualArgAlternative::Identified {
+                ident,
+                pat,
+                colon_token: typed_arg.colon_token,
+                ty: typed_arg.ty.clone(),
+            },
+            default: None,
+        })
+    }
+
+    // Top-level `let` statements of an intermediate body binding an identifier with an explicit
+    // type are forwarded to the next steps, as immutable bindings.
+    pub fn extract_derived_values(body: &IntermediateBody) -> UsualArgExtractionResult {
+        let stmts = Block::parse_within
+            .parse2(body.stmts.clone())
+            .map_err(|error| error.to_compile_error())?;
+        let mut derived = Vec::<UsualArg>::new();
+        for stmt in &stmts {
+            let Stmt::Local(Local {
+                pat: Pat::Type(typed_pat),
+                init,
+                ..
+            }) = stmt
+            else {
+                continue;
+            };
+            let Pat::Ident(pat_ident) = &*typed_pat.pat else {
+                continue;
+            };
+            if pat_ident.by_ref.is_some() || pat_ident.subpat.is_some() {
+                continue;
             }
-            _ => {
-                let message = "Only basic identifier pattern is supported";
-                let span = typed_arg.colon_token.span;
-                Err(quote_spanned! { span => compile_error!(#message); })
+            // Assignments later in the body are not followed, so the value must be given here.
+            if init.is_none() {
+                let message = format!(
+                    "derived value `{}` must be initialized where it is declared",
+                    pat_ident.ident
+                );
+                let span = pat_ident.ident.span();
+                return Err(quote_spanned! { span => compile_error!(#message); });
             }
+            derived.push(UsualArg {
+                attrs: Vec::new(),
+                alt: UsualArgAlternative::Identified {
+                    ident: pat_ident.ident.clone(),
+                    pat: None,
+                    colon_token: typed_pat.colon_token,
+                    ty: typed_pat.ty.clone(),
+                },
+                default: None,
+            });
         }
+        Ok(derived)
     }
 
-    fn extract_receiver(receiver: &Receiver) -> Result<UsualArg, TokenStream> {
+    fn extract_receiver(receiver: &Receiver, ident: &Ident) -> Result<UsualArg, TokenStream> {
+        let typed = receiver
+            .colon_token
+            .map(|colon_token| (colon_token, receiver.ty.clone()));
         let alt = UsualArgAlternative::Receiver {
             reference: receiver.reference.clone(),
             mutability: receiver.mutability,
             self_token: receiver.self_token,
+            typed,
+            ident: ident.clone(),
         };
-        if let Some(colon_token) = receiver.colon_token {
-            let message = "Complex receivers type are not supported";
-            let span = colon_token.span;
-            Err(quote_spanned! { span => compile_error!(#message); })
-        } else {
-            Ok(UsualArg {
-                attrs: receiver.attrs.clone(),
-                alt,
-            })
-        }
+        Ok(UsualArg {
+            attrs: receiver.attrs.clone(),
+            alt,
+            default: None,
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use quote::quote;
-    use syn::{token::Comma, FnArg};
+    use syn::token::Comma;
 
     use super::UsualArg;
+    use crate::model::config::GenerationConfig;
+    use crate::model::section::{Section, SectionArg};
 
     #[test]
     fn parse_usual_args() {
-        let tokens = quote!(text: &'a str, n: i32);
+        let receiver = GenerationConfig::default().receiver;
+        let tokens = quote!(f(text: &'a str, n: i32));
 
-        let punctuated = asserts::tokens_are_parsable_punctuated_as::<FnArg, Comma>(tokens);
+        let section = asserts::tokens_are_parsable_as::<Section>(tokens);
 
-        let args =
-            UsualArg::extract_usual_args(&punctuated).expect("Should not have conversion issue");
+        let args = UsualArg::extract_usual_args(&section, &receiver)
+            .expect("Should not have conversion issue");
 
         assert_eq!(2, args.len());
         assert_eq!("text", args[0].name().as_str());
         assert_eq!("n", args[1].name().as_str());
 
-        let tokens = quote!(pair: (usize, String), dates: Vec<Date>,);
+        let tokens = quote!(f(pair: (usize, String), dates: Vec<Date>,));
 
-        let punctuated = asserts::tokens_are_parsable_punctuated_as::<FnArg, Comma>(tokens);
+        let section = asserts::tokens_are_parsable_as::<Section>(tokens);
 
-        let args =
-            UsualArg::extract_usual_args(&punctuated).expect("Should not have conversion issue");
+        let args = UsualArg::extract_usual_args(&section, &receiver)
+            .expect("Should not have conversion issue");
 
         assert_eq!(2, args.len());
         assert_eq!("pair", args[0].name().as_str());
@@ -212,17 +367,71 @@ mod tests {
 
         let tokens = quote!(text: &'a str, n: i32;);
 
-        asserts::tokens_are_not_matching_punctuated::<FnArg, Comma>(tokens, "unexpected token");
+        asserts::tokens_are_not_matching_punctuated::<SectionArg, Comma>(
+            tokens,
+            "unexpected token",
+        );
 
-        let tokens = quote!(&'a self, text: &'a str);
+        let tokens = quote!(f(&'a self, text: &'a str));
 
-        let punctuated = asserts::tokens_are_parsable_punctuated_as::<FnArg, Comma>(tokens);
+        let section = asserts::tokens_are_parsable_as::<Section>(tokens);
 
-        let args =
-            UsualArg::extract_usual_args(&punctuated).expect("Should not have conversion issue");
+        let args = UsualArg::extract_usual_args(&section, &receiver)
+            .expect("Should not have conversion issue");
 
         assert_eq!(2, args.len());
         assert_eq!("self_", args[0].name().as_str());
         assert_eq!("text", args[1].name().as_str());
+
+        let tokens = quote!(f(self: Box<Self>, n: usize));
+
+        let section = asserts::tokens_are_parsable_as::<Section>(tokens);
+
+        let args = UsualArg::extract_usual_args(&section, &receiver)
+            .expect("Should not have conversion issue");
+
+        assert_eq!(2, args.len());
+        assert_eq!("self_", args[0].name().as_str());
+        let mut ty = proc_macro2::TokenStream::new();
+        args[0].push_type_to_tokens(Some(&syn::parse_quote!(MyInt)), &mut ty);
+        assert_eq!("Box < MyInt >", ty.to_string());
+
+        let tokens = quote!(f(n: usize = 1, text: &'static str = ""));
+
+        let section = asserts::tokens_are_parsable_as::<Section>(tokens);
+
+        let args = UsualArg::extract_usual_args(&section, &receiver)
+            .expect("Should not have conversion issue");
+
+        assert_eq!(2, args.len());
+        assert!(args.iter().all(|arg| arg.default().is_some()));
+
+        let tokens = quote!(f((a, b): (usize, bool), mut buf: Vec<u8>, Point { x, y }: Point));
+
+        let section = asserts::tokens_are_parsable_as::<Section>(tokens);
+
+        let args = UsualArg::extract_usual_args(&section, &receiver)
+            .expect("Should not have conversion issue");
+
+        assert_eq!(3, args.len());
+        assert_eq!("__f_0", args[0].name().as_str());
+        assert_eq!("buf", args[1].name().as_str());
+        assert_eq!("__f_2", args[2].name().as_str());
+        assert!(args.iter().all(|arg| arg.pattern().is_some()));
+
+        let tokens = quote!(r#type((a, b): (u8, u8)));
+
+        let section = asserts::tokens_are_parsable_as::<Section>(tokens);
+
+        let args = UsualArg::extract_usual_args(&section, &receiver)
+            .expect("Should not have conversion issue");
+
+        assert_eq!("__type_0", args[0].name().as_str());
+
+        let tokens = quote!(f((a, b): (usize, bool))?);
+
+        let section = asserts::tokens_are_parsable_as::<Section>(tokens);
+
+        assert!(UsualArg::extract_usual_args(&section, &receiver).is_err());
     }
 }
@@ -146,6 +146,328 @@ mod model;
  *     .as_post()
  *     .with_text_body("Hello world".to_string());
  * ```
+ * Alternatives can also be written as separate method chains starting with the same sections.
+ * They are merged into the same tree, as long as the shared sections are declared identically:
+ * ```rust
+ * assemblist! {
+ *     fn greet(name: &str).politely() -> String { format!("Good morning, {name}.") }
+ *     fn greet(name: &str).casually() -> String { format!("Hi {name}!") }
+ * }
+ * ```
+ *
+ * Each alternative can carry its own attributes, a `pub` or `pub(crate)` visibility, and the
+ * `async` or `unsafe` qualifiers, which apply to the method it generates:
+ * ```rust
+ * fn new_http_request_to(url: Uri).{
+ *     #[must_use]
+ *     pub(crate) fn as_get() -> GetHttpRequest { GetHttpRequest { url } }
+ *
+ *     #[cfg(feature = "json")]
+ *     fn as_post().with_json_body(json: JsonValue) -> PostHttpRequest { PostHttpRequest { url, json } }
+ *
+ *     unsafe fn as_raw(bytes: *const u8, len: usize) -> RawHttpRequest { RawHttpRequest::new(url, bytes, len) }
+ * }
+ * ```
+ * `#[cfg(…)]` attributes also apply to the intermediate types and modules of the alternative, and
+ * remove its method chains from the documentation when disabled.
+ *
+ * # Elide lifetimes
+ *
+ * Lifetimes of references can be elided in arguments as in ordinary functions. Each elided
+ * lifetime becomes a distinct lifetime parameter of the section. Elided lifetimes in the return
+ * type follow the usual rules: they refer to the lifetime of a `&self` receiver, or else to the
+ * only lifetime appearing in the arguments of the method chain:
+ * ```rust
+ * pub fn skip_in(text: &str).first(n: usize) -> &str {
+ *     &text[n..]
+ * }
+ * ```
+ * When the arguments hold several lifetimes and there is no `&self` receiver, the lifetime
+ * returned must be named, otherwise a missing lifetime specifier error is reported.
+ *
+ * # Use patterns in arguments
+ *
+ * As in ordinary functions, arguments can be declared with patterns such as `(a, b): (usize, bool)`,
+ * `Point { x, y }: Point` or `mut buffer: Vec<u8>`. The bindings they introduce are available in the
+ * body of the method chain:
+ * ```rust
+ * pub fn translate(Point { x, y }: Point).by((dx, dy): (i32, i32)) -> Point {
+ *     Point { x: x + dx, y: y + dy }
+ * }
+ * ```
+ * Optional sections only accept identifiers, possibly declared as `mut`.
+ *
+ * # Pass `impl Trait` arguments
+ *
+ * Arguments of any section can be declared with `impl Trait` types, so that closures and iterators
+ * can be passed at any step of a method chain. Each of them becomes a distinct type parameter of
+ * its section:
+ * ```rust
+ * pub fn retain_from(items: impl IntoIterator<Item = u32>)
+ *     .matching(predicate: impl FnMut(&u32) -> bool) -> Vec<u32>
+ * {
+ *     items.into_iter().filter(predicate).collect()
+ * }
+ * ```
+ * Sections that can be skipped do not accept them. The arguments of the final section are not
+ * stored, so they keep their `impl Trait` types and the final method can still be called with
+ * explicit generic arguments.
+ *
+ * # Declare type-only sections
+ *
+ * Generic parameters do not need to be stored by intermediate types. A section can therefore
+ * declare a type parameter that only appears in the return type of its method chains:
+ * ```rust
+ * fn parse().as_type<T: FromStr>().from(text: &str) -> Option<T> {
+ *     text.parse().ok()
+ * }
+ *
+ * let port = parse().as_type::<u16>().from("8080");
+ * ```
+ *
+ * # Bound generic parameters
+ *
+ * Any section can end with a `where` clause, right before the next section. Its bounds apply to
+ * the rest of the method chain, together with those of the previous sections and of the enclosing
+ * implementation:
+ * ```rust
+ * impl<T> Pair<T> where T: Clone {
+ *     pub fn zip<U>(&self, right: U) where U: Clone
+ *         .labelled<L>(label: L) where L: Into<String>
+ *         .into_tuple() -> (T, Vec<U>, String)
+ *     {
+ *         (self.left.clone(), vec![right.clone(), right], label.into())
+ *     }
+ * }
+ * ```
+ * Sections that can be skipped do not accept them.
+ *
+ * # Await between sections
+ *
+ * An `async fn` makes the last sections of its method chains async. Any other section can be
+ * made async on its own, with `.async name(…)` or `async fn name(…)` inside alternative blocks.
+ * Intermediate sections can also run a body before the method chain continues:
+ * ```rust
+ * pub async fn connect_to(host: &'static str)
+ *     .async authenticated_with(token: u32) {
+ *         check_token(host, token).await;
+ *     }
+ *     .fetch(id: u32) -> String {
+ *         download(host, token, id).await
+ *     }
+ *
+ * let item = connect_to("localhost").authenticated_with(42).await.fetch(7).await;
+ * ```
+ * Neither the first section nor sections that can be skipped accept `async` or a body.
+ *
+ * # Validate intermediate sections
+ *
+ * The body of an intermediate section can short-circuit the method chain by returning
+ * `Result<_, E>` or `Option<_>`, where `_` stands for the next step. Callers then use `?` between
+ * steps:
+ * ```rust
+ * fn define_movie<'a>(name: &'a str)
+ *     .released_in(release_year: usize) -> Result<_, MovieError> {
+ *         if release_year < 1888 {
+ *             Err(MovieError::TooEarly(release_year))?
+ *         }
+ *     }
+ *     .directed_by(director_name: &'a str) -> Movie
+ * {
+ *     Movie { name: name.to_string(), release_year, director_name: director_name.to_string() }
+ * }
+ *
+ * let movie = define_movie("The Lobster").released_in(2015)?.directed_by("Yorgos Lanthimos");
+ * ```
+ *
+ * # Derive values in intermediate sections
+ *
+ * Top-level `let` statements of an intermediate body that bind an identifier with an explicit
+ * type and an initial value are stored by the method chain, so that the following sections can
+ * use them without computing them again:
+ * ```rust
+ * fn request_to(host: &str)
+ *     .at(path: &str) {
+ *         let url: String = format!("https://{host}/{path}");
+ *     }
+ *     .{
+ *         fn get() -> String { format!("GET {url}") }
+ *         fn delete() -> String { format!("DELETE {url}") }
+ *     }
+ * ```
+ * Other statements, including untyped `let` statements and bindings with patterns, remain
+ * local to the body. Derived values are immutable in the following sections and cannot reuse the
+ * name of a value already stored by the method chain.
+ *
+ * # Skip optional sections
+ *
+ * A section followed by `?` is optional: callers may either call it or directly continue with
+ * the next section. Inside the body, each argument of an optional section has type `Option<T>`
+ * and is `None` whenever the section was skipped.
+ * ```rust
+ * fn define_movie<'a>(name: &'a str)
+ *     .released_in(release_year: usize)?
+ *     .directed_by(director_name: &'a str) -> Movie
+ * {
+ *     Movie {
+ *         name: name.to_string(),
+ *         release_year: release_year.unwrap_or_default(),
+ *         director_name: director_name.to_string(),
+ *     }
+ * }
+ * ```
+ * Both following calls are then valid:
+ * ```rust
+ * let movie = define_movie("The Lobster").directed_by("Yorgos Lanthimos");
+ * let movie = define_movie("The Lobster").released_in(2015).directed_by("Yorgos Lanthimos");
+ * ```
+ * The first section of a method chain cannot be optional, neither can be the last one. Optional
+ * sections cannot declare generic parameters either.
+ *
+ * # Provide default values
+ *
+ * Arguments can also be given a default value. When all arguments of a section have one, the
+ * section can be skipped and the body then receives the default values:
+ * ```rust
+ * pub fn replace_in<'a>(text: &'a str)
+ *     .occurrences_of(pattern: &'a str)
+ *     .with(replacement: &'a str)
+ *     .at_most(n: usize = text.len())
+ *     .times() -> String
+ * {
+ *     text.replacen(pattern, replacement, n)
+ * }
+ * ```
+ * Default values are evaluated only when the section is skipped and may refer to the arguments of
+ * previous sections. The same restrictions as for optional sections apply.
+ *
+ * # Repeat sections
+ *
+ * A section followed by `*` can be called any number of times, including zero. Its arguments are
+ * accumulated in a `Vec` named after the section, holding a tuple per call when the section has
+ * several arguments:
+ * ```rust
+ * fn request_to<'a>(url: &'a str)
+ *     .header(name: &'a str, value: &'a str)*
+ *     .send() -> Request
+ * {
+ *     Request { url: url.to_string(), headers: header }
+ * }
+ * ```
+ * It is then possible to write:
+ * ```rust
+ * let request = request_to("/home").header("Accept", "text/html").header("Host", "localhost").send();
+ * ```
+ * An upper bound can be given as `*[..=N]`, in which case calling the section more than `N` times
+ * fails to compile. The same restrictions as for optional sections apply.
+ *
+ * # Call sections in any order
+ *
+ * When the order of some sections does not matter, they can be gathered in an `any` group. Each
+ * of them must then be called exactly once, in any order, before continuing with the rest of the
+ * chain:
+ * ```rust
+ * fn define_movie<'a>(name: &'a str).{
+ *     any
+ *     fn released_in(release_year: usize);
+ *     fn directed_by(director_name: &'a str);
+ * }.build() -> Movie
+ * {
+ *     Movie { name: name.to_string(), release_year, director_name: director_name.to_string() }
+ * }
+ * ```
+ * Both `define_movie("The Lobster").released_in(2015).directed_by("Yorgos Lanthimos").build()` and
+ * `define_movie("The Lobster").directed_by("Yorgos Lanthimos").released_in(2015).build()` are
+ * then valid. Every order is generated separately, which is why a group cannot contain more than
+ * 5 sections. Sections of an `any` group cannot be skipped.
+ *
+ * # Derive traits on intermediate types
+ *
+ * Intermediate types implement no trait by default. The `derive` option implements `Clone`,
+ * `Copy`, `Debug`, `PartialEq`, `Eq`, `Hash`, `PartialOrd` or `Ord` on all of them, so that a
+ * common prefix can be built once and then forked:
+ * ```rust
+ * #[assemblist(derive(Clone, Debug))]
+ * fn define_movie<'a>(name: &'a str)
+ *     .released_in(release_year: usize)
+ *     .directed_by(director_name: &'a str) -> Movie
+ * {
+ *     Movie { name: name.to_string(), release_year, director_name: director_name.to_string() }
+ * }
+ *
+ * let prefix = define_movie("The Lobster").released_in(2015);
+ * let movie = prefix.clone().directed_by("Yorgos Lanthimos");
+ * ```
+ * Each implementation only holds when the stored arguments implement the trait, so that method
+ * chains taking other arguments keep compiling. Options declared on an `impl` or `trait` block
+ * apply to all its method chains.
+ *
+ * # Inspect and resume partial method chains
+ *
+ * The `accessors` option gives intermediate types read-only getters named after the stored
+ * arguments, along with `into_parts` and `from_parts` to convert them from and into tuples:
+ * ```rust
+ * #[assemblist(accessors)]
+ * fn define_movie<'a>(name: &'a str)
+ *     .released_in(release_year: usize)
+ *     .directed_by(director_name: &'a str) -> Movie
+ * {
+ *     Movie { name: name.to_string(), release_year, director_name: director_name.to_string() }
+ * }
+ *
+ * let partial = define_movie("The Lobster").released_in(2015);
+ * assert_eq!(&2015, partial.release_year());
+ * let parts: (&str, usize) = partial.into_parts();
+ * let movie = define_movie::released_in::Output::from_parts(parts).directed_by("Yorgos Lanthimos");
+ * ```
+ * Tuples list the stored values in declaration order. The values of a repeated section are read
+ * with a getter suffixed by `_list`, and arguments declared with patterns only appear in the
+ * tuples. A getter cannot share its name with a section that continues the method chain.
+ *
+ * # Reuse partial method chains
+ *
+ * The `by_ref` option makes the methods continuing a method chain take `&self` and clone the
+ * arguments stored so far, so that a single prefix can produce many values:
+ * ```rust
+ * #[assemblist(by_ref)]
+ * fn new_http_request_to(url: Uri)
+ *     .from<'a>(user_agent: &'a str)
+ *     .with_token(token: String) -> HttpRequest
+ * {
+ *     HttpRequest { url, user_agent: user_agent.to_string(), token }
+ * }
+ *
+ * let template = new_http_request_to(url).from("FireFox");
+ * for token in tokens {
+ *     send(template.with_token(token));
+ * }
+ * ```
+ * A continuation can only be called when the arguments stored before it implement `Clone`.
+ * Repeated sections still consume their intermediate type.
+ *
+ * # Name intermediate types
+ *
+ * By default, each intermediate type is called `Output` and lives in a module named after the
+ * section returning it. With `layout = "flat"`, intermediate types are instead declared next to the
+ * method chain and named after the sections called so far, prefixed by the implementing type or
+ * trait if any. The `naming` option wraps these names in a scheme, and implies the flat layout.
+ * An intermediate section can also be named explicitly with `as`:
+ * ```rust
+ * #[assemblist(naming = "{}Builder")]
+ * fn new_http_request_to(url: Uri)
+ *     .from<'a>(user_agent: &'a str) as RequestDraft
+ *     .with_token(token: String) -> HttpRequest
+ * {
+ *     HttpRequest { url, user_agent: user_agent.to_string(), token }
+ * }
+ *
+ * let partial: NewHttpRequestToBuilder = new_http_request_to(url);
+ * let draft: RequestDraft<'_> = partial.from("FireFox");
+ * ```
+ * Flat intermediate types share the visibility and attributes of the method chain. Sections of an
+ * `any` group and final sections cannot be named. When two method chains of the same invocation
+ * produce the same name, such as `a_b(…).c(…)` and `a(…).b_c(…)`, one of them must be renamed
+ * with `as`.
  *
  * # Use method chains in inherent implementations
  *
@@ -162,12 +484,14 @@ mod model;
  * }
  * ```
  *
- * Assemblist also supports the use of the self receiver in three standard forms: `self`, `&'a self`, and `&'a mut self`.
- * However, there are a couple of important rules to follow:
- *  - The self parameter must appear as the first argument in the first method of the chain.
- *  - Inside method bodies, the receiver is referred to using `self_` instead of the conventional `self`.
+ * Assemblist also supports the use of the self receiver, either in the three standard forms `self`, `&self`
+ * and `&mut self`, or with an explicit type such as `self: Box<Self>`, `self: Rc<Self>` or `self: Pin<&mut Self>`.
+ * The self parameter must appear as the first argument in the first method of the chain. Method
+ * bodies of later sections then refer to the receiver with `self` as usual, including inside
+ * closures and macro invocations such as `format!("{self:?}")`. The stored receiver is also
+ * available as `self_`, unless another name is configured (see [below](#configure-code-generation)).
  *
- * Here’s an example showcasing this convention:
+ * Here’s an example:
  * ```rust
  * struct MyVec<T> { _inner: Vec<T> }
  *
@@ -175,10 +499,10 @@ mod model;
  *    impl<T> MyVec<T> where T: Debug {
  *         pub fn at<'a>(&'a mut self, index: usize).{
  *             fn insert(element: T) {
- *                 self_._inner.insert(index, element)
+ *                 self._inner.insert(index, element)
  *             }
  *             fn remove() -> T {
- *                 self_._inner.remove(index)
+ *                 self._inner.remove(index)
  *             }
  *         }
  *     }
@@ -191,6 +515,20 @@ mod model;
  * v.at(3).remove();
  * ```
  *
+ * Inside a method chain, `Self` always designates the implementing type, even in the arguments,
+ * return types and bodies of later sections:
+ * ```rust
+ * struct Journal { lines: Vec<String> }
+ *
+ * assemblist! {
+ *     impl Journal {
+ *         pub fn new().with_capacity(n: usize) -> Self {
+ *             Self { lines: Vec::with_capacity(n) }
+ *         }
+ *     }
+ * }
+ * ```
+ *
  * Note that it is possible to declare multiple inherent implementations inside the same `assemblist!`
  * macro invocation and to mix them with root method chains.
  *
@@ -241,56 +579,110 @@ mod model;
  * }
  * ```
  *
- * # Current limitations
- *
- * ## No implicit lifetimes
+ * # Use method chains in trait implementations
  *
- * Assemblist does not handle implicit lifetimes for now so you must declare them explicitely. Each method
- * inside the method chain can carry its own lifetimes:
+ * Method chains can also start with a method of a trait implementation. The trait method then
+ * returns the first intermediate type, which the trait can name through an associated type.
+ * Since other types can implement the same trait, the module of this type is prefixed by the
+ * whole implementing type in snake case, such as `vec_u8_describe` for `Vec<u8>`, and flat type
+ * names start with it in camel case, such as `VecU8Describe`.
+ * Associated types and constants declared inside the implementation are kept as is:
  * ```rust
- * fn pour_items_from<'a, T>(source: &'a mut Vec<T>)
- *     .starting_at(n: usize)
- *     .into<'b>(destination: &'b mut Vec<T>)
- * {
- *     for item in source.drain(n..) {
- *         destination.push(item);
+ * trait Describe {
+ *     type Describer<'a> where Self: 'a;
+ *     fn describe(&self) -> Self::Describer<'_>;
+ * }
+ *
+ * assemblist! {
+ *     impl Describe for Point {
+ *         type Describer<'a> = point_describe::Output<'a>;
+ *
+ *         fn describe(&self).with(prefix: &str) -> String {
+ *             format!("{prefix}{self:?}")
+ *         }
  *     }
  * }
  * ```
  *
- * ## No patterns for arguments
+ * # Declare method chains in traits
  *
- * You cannot use patterns for methods arguments as in `f((a, b): (usize, bool))` or in `g(ref r: f64)`. If
- * you need to do this, just use plain argument names and destructure them inside the body:
+ * Method chains can be declared in trait definitions as well. Each chain ending with `;` is
+ * implemented through a flattened method, named after its sections joined by underscores, which
+ * takes the arguments of all sections at once. A chain ending with a body provides a default
+ * implementation of its flattened method:
  * ```rust
- * fn f(pair: (usize, bool)).g(x: f64) {
- *     let (a, b) = pair;
- *     let ref r = x;
+ * assemblist! {
+ *     pub trait Repository {
+ *         fn find(&self, id: u64).{
+ *             fn with_deleted(flag: bool) -> Option<Item>;
+ *             fn first() -> Option<Item> {
+ *                 self.find_with_deleted(id, false)
+ *             }
+ *         }
+ *     }
  * }
- * ```
  *
- * ## No complex receiver arguments
+ * impl Repository for Memory {
+ *     fn find_with_deleted(&self, id: u64, flag: bool) -> Option<Item> {
+ *         /* code */
+ *     }
+ * }
  *
- * For now, method chains inside inherent implementations cannot declare a complex receiver argument. Only `self`,
- * `&'a self` and `&'a mut self` are allowed. So this example fails to compile:
- * ```compile_fail
- * struct MyInt(isize);
+ * let item = memory.find(2).with_deleted(true);
+ * ```
+ * Since every order would need its own flattened method, `any` groups are not supported in trait
+ * definitions.
+ *
+ * # Configure code generation
  *
+ * Inner attributes at the top of an `assemblist!` invocation tune the code generated for all its
+ * method chains:
+ * ```rust
  * assemblist! {
- *     impl MyInt {
- *         fn is_between(self: Box<Self>, a: isize).and(b: isize) { a <= self.0 && self.0 <= b }
+ *     #![assemblist(inline = "always", receiver = "this", doc_separator = "===")]
+ *
+ *     impl Odometer {
+ *         /// Count from the start of the odometer.
+ *         ///===
+ *         /// Provide a number of steps.
+ *         fn count(&self).times(n: usize) -> usize { this.start + n }
  *     }
  * }
  * ```
+ *  - `inline` sets the attribute of intermediate methods: `"hint"` for `#[inline]` (the default),
+ *    `"always"`, `"never"`, or `"off"` for none.
+ *  - `receiver` names the stored receiver inside method bodies, `self_` by default.
+ *  - `doc_separator` is the line separating the descriptions of consecutive sections, `---` by default.
+ *  - `layout` sets the layout of the method chains that do not choose theirs, `"nested"` by default.
+ *  - `module_visibility` sets the visibility of the modules of the nested layout, such as
+ *    `"pub(crate)"`, which otherwise share the visibility of their method chain.
+ *
+ * Nested modules only see the items of the enclosing module. Method chains declared inside a
+ * function body, such as a test, can refer to the types declared next to them with the flat layout:
+ * ```rust
+ * fn book_tickets() {
+ *     struct Ticket { price: u32, seat: u16 }
+ *
+ *     assemblist! {
+ *         #![assemblist(layout = "flat")]
+ *
+ *         fn book(price: u32).seat(seat: u16) -> Ticket { Ticket { price, seat } }
+ *     }
+ *
+ *     assert_eq!(12, book(30).seat(12).seat);
+ * }
+ * ```
  *
- * ## Avoid method name clash
+ * # Current limitations
+ *
+ * ## Share method names carefully
  *
  * The `assemblist!` macro generates a tree of inner modules, each containing custom types and
  * implementations. These modules are named based on the sections of your method chains.
  *
- * Because of this structure, multiple method chains within the same root module **cannot
- * start with the same method name** --- whether they belong to inherent implementations or not.
- * This restriction prevents naming conflicts in the generated modules.
+ * Method chains starting with the same method name are merged into a single tree, so they must
+ * declare the sections they have in common identically. Chains declared in different blocks are
+ * never merged: they would share their module, so they are rejected.
  *
  * ```compile_fail
  * assemblist! {
@@ -298,17 +690,13 @@ mod model;
  *         fn f(/*args*/).y(/*args*/) { /* code */ }
  *     }
  *
- *     /* conflict: other method chain already starts with `f` */
- *     fn f(/*args*/).x(/*args*/) { /* code */ }
- *
- *     impl MyVec<T> {
- *         /* conflict: other method chain already starts with `f` */
+ *     impl<T> MyVec<T> {
+ *         /* conflict: both method chains would share the module `f` */
  *         fn f(/*args*/).z(/*args*/) { /* code */ }
  *     }
  * }
  * ```
- * If you need multiple method chains with the same starting name, declare them inside
- * separate modules to avoid conflicts.
+ * If you need such method chains, declare them inside separate modules to avoid conflicts.
  */
 #[proc_macro]
 pub fn assemblist(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
@@ -3,16 +3,20 @@ use quote::{quote, ToTokens};
 use syn::{parse::Parse, Attribute, Expr, ExprLit, Lit, MetaNameValue};
 
 const DOC_ATTRIBUTE_NAME: &str = "doc";
-const SEPARATION: &str = "\"---\"";
+const CFG_ATTRIBUTE_NAME: &str = "cfg";
 
+#[derive(Clone)]
 pub struct AttributeBlock {
     attrs: Vec<Attribute>,
 }
 
+#[derive(Clone)]
 pub struct DocumentationSection {
     lines: Vec<Attribute>,
 }
 
+// Until split, all the documentation lines belong to a single section.
+#[derive(Clone)]
 pub struct DocumentationBlock {
     sections: Vec<DocumentationSection>,
 }
@@ -23,38 +27,23 @@ pub struct DocumentationBlockView<'a> {
     sections: &'a [DocumentationSection],
 }
 
-enum AttributeKind {
-    Doc,
-    DocSeparation,
-    Other,
-}
-
-fn classify_doc(nvm: &MetaNameValue) -> AttributeKind {
-    if let Expr::Lit(ExprLit {
-        lit: Lit::Str(str), ..
-    }) = &nvm.value
-    {
-        let mut tokens = TokenStream::new();
-        str.to_tokens(&mut tokens);
-        if tokens.to_string() == SEPARATION {
-            AttributeKind::DocSeparation
-        } else {
-            AttributeKind::Doc
-        }
-    } else {
-        AttributeKind::Doc
+fn is_doc(attr: &Attribute) -> bool {
+    match &attr.meta {
+        syn::Meta::NameValue(name_value) => name_value.path.is_ident(DOC_ATTRIBUTE_NAME),
+        _ => false,
     }
 }
 
-fn classify(attr: &Attribute) -> AttributeKind {
-    if let syn::Meta::NameValue(name_value) = &attr.meta {
-        if let Some(name) = name_value.path.get_ident() {
-            if name == DOC_ATTRIBUTE_NAME {
-                return classify_doc(name_value);
-            }
-        }
+fn is_separation(attr: &Attribute, separator: &str) -> bool {
+    match &attr.meta {
+        syn::Meta::NameValue(MetaNameValue {
+            value: Expr::Lit(ExprLit {
+                lit: Lit::Str(str), ..
+            }),
+            ..
+        }) => str.value() == separator,
+        _ => false,
     }
-    AttributeKind::Other
 }
 
 impl Parse for AttributeBlock {
@@ -95,9 +84,29 @@ impl ToTokens for AttributeBlock {
 }
 
 impl AttributeBlock {
+    pub fn new() -> Self {
+        Self { attrs: Vec::new() }
+    }
+
     pub fn is_empty(&self) -> bool {
         self.attrs.is_empty()
     }
+
+    // Removes the attributes named `name` from the block.
+    pub fn take_matching(&mut self, name: &str) -> Vec<Attribute> {
+        let (matching, others) = std::mem::take(&mut self.attrs)
+            .into_iter()
+            .partition(|attr| attr.path().is_ident(name));
+        self.attrs = others;
+        matching
+    }
+
+    // The `#[cfg(…)]` attributes of the block, which also apply to the items generated for it.
+    pub fn cfgs(&self) -> impl Iterator<Item = &Attribute> {
+        self.attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident(CFG_ATTRIBUTE_NAME))
+    }
 }
 
 impl DocumentationBlock {
@@ -108,24 +117,46 @@ impl DocumentationBlock {
     }
 
     pub fn extract_from(attr_block: &mut AttributeBlock) -> Self {
-        let mut attrs = Vec::<Attribute>::new();
-        std::mem::swap(&mut attrs, &mut attr_block.attrs);
+        let (lines, others) = std::mem::take(&mut attr_block.attrs)
+            .into_iter()
+            .partition::<Vec<_>, _>(is_doc);
+        attr_block.attrs = others;
+        if lines.is_empty() {
+            return Self::new();
+        }
+        Self {
+            sections: vec![DocumentationSection { lines }],
+        }
+    }
+
+    // Splits the documentation into the descriptions of consecutive sections, at lines
+    // consisting of `separator`.
+    pub fn split(&mut self, separator: &str) {
         let mut sections = Vec::<DocumentationSection>::new();
         let mut lines = Vec::<Attribute>::new();
-        for attr in attrs {
-            match classify(&attr) {
-                AttributeKind::DocSeparation => {
-                    sections.push(DocumentationSection { lines });
-                    lines = Vec::<Attribute>::new();
-                }
-                AttributeKind::Doc => lines.push(attr),
-                AttributeKind::Other => attr_block.attrs.push(attr),
+        for attr in self.sections.drain(..).flat_map(|section| section.lines) {
+            if is_separation(&attr, separator) {
+                sections.push(DocumentationSection { lines });
+                lines = Vec::<Attribute>::new();
+            } else {
+                lines.push(attr);
             }
         }
         if !lines.is_empty() {
             sections.push(DocumentationSection { lines });
         }
-        Self { sections }
+        self.sections = sections;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    // The documentation of the sections following the first `count` ones.
+    pub fn sections_after(&self, count: usize) -> Self {
+        Self {
+            sections: self.sections.iter().skip(count).cloned().collect(),
+        }
     }
 
     pub fn create_view_starting_at(&self, depth: usize) -> DocumentationBlockView<'_> {
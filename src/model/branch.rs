@@ -6,15 +6,34 @@ use quote::ToTokens;
 use syn::parse::{Parse, ParseBuffer, ParseStream};
 use syn::spanned::Spanned;
 use syn::token::Brace;
-use syn::{braced, Error, Result, ReturnType, Token};
+use syn::{braced, Error, Result, ReturnType, Token, Visibility};
 
+// Beyond this size, the number of generated permutations would become unreasonable.
+const MAX_ANY_GROUP_SIZE: usize = 5;
+
+mod kw {
+    syn::custom_keyword!(any);
+}
+
+#[derive(Clone)]
 pub struct DocumentedBranch {
     pub doc_block: DocumentationBlock,
     pub branch: Branch,
 }
 
+// Statements run by an intermediate section before continuing the method chain. When it returns
+// `Result<_, E>` or `Option<_>`, the section can short-circuit the method chain.
+#[derive(Clone)]
+pub struct IntermediateBody {
+    pub output: ReturnType,
+    pub brace: Brace,
+    pub stmts: TokenStream,
+}
+
+#[derive(Clone)]
 pub enum BranchTail {
     Alternative {
+        body: Option<IntermediateBody>,
         dot: Token![.],
         rest: Box<(DocumentedBranch, Vec<DocumentedBranch>)>,
     },
@@ -23,54 +42,224 @@ pub enum BranchTail {
         brace: Brace,
         body: TokenStream,
     },
+    // Only allowed in trait definitions.
+    Declaration {
+        output: ReturnType,
+        semi_token: Token![;],
+    },
+}
+
+impl BranchTail {
+    pub fn is_final(&self) -> bool {
+        !matches!(self, BranchTail::Alternative { .. })
+    }
+}
+
+impl DocumentedBranch {
+    pub fn split_documentation(&mut self, separator: &str) {
+        self.doc_block.split(separator);
+        if let BranchTail::Alternative { rest, .. } = &mut self.branch.tail {
+            rest.0.split_documentation(separator);
+            for branch in &mut rest.1 {
+                branch.split_documentation(separator);
+            }
+        }
+    }
 }
 
+#[derive(Clone)]
 pub struct Branch {
     pub section: Section,
     pub tail: BranchTail,
 }
 
-fn try_parse_brace(input: ParseStream) -> Result<ParseBuffer<'_>> {
+fn try_parse_brace(input: ParseStream<'_>) -> Result<ParseBuffer<'_>> {
     let content: ParseBuffer<'_>;
     let _: Brace = braced!(content in input);
     Ok(content)
 }
 
-fn try_parse_branches(
-    input: ParseStream,
-) -> Result<Box<(DocumentedBranch, Vec<DocumentedBranch>)>> {
-    let mut attr_block: AttributeBlock = input.parse()?;
-    let doc_block = DocumentationBlock::extract_from(&mut attr_block);
-
-    if !attr_block.is_empty() {
-        return Err(Error::new(
-            input.span(),
-            "only document attributes are allowed here",
-        ));
+// Alternatives are nested in modules, where only these visibilities keep their meaning.
+fn check_branch_visibility(vis: &Visibility) -> Result<()> {
+    match vis {
+        Visibility::Public(_) | Visibility::Inherited => Ok(()),
+        Visibility::Restricted(restricted)
+            if restricted.in_token.is_none() && restricted.path.is_ident("crate") =>
+        {
+            Ok(())
+        }
+        Visibility::Restricted(restricted) => Err(Error::new(
+            restricted.pub_token.span,
+            "alternatives can only be `pub` or `pub(crate)`",
+        )),
     }
+}
 
+// ⟨attr⟩ ⟨visibility⟩ ⟨?async⟩ ⟨?unsafe⟩ fn ⟨branch⟩
+fn parse_fn_branch(attr_block: AttributeBlock, input: ParseStream) -> Result<Branch> {
+    let vis: Visibility = input.parse()?;
+    check_branch_visibility(&vis)?;
+    let asyncness: Option<Token![async]> = input.parse()?;
+    let unsafety: Option<Token![unsafe]> = input.parse()?;
     input.parse::<Token![fn]>()?;
+    let mut branch: Branch = input.parse()?;
+    if let Some(async_token) = asyncness {
+        if branch.section.asyncness.is_some() {
+            return Err(Error::new(async_token.span, "duplicate `async`"));
+        }
+        if branch.section.is_skippable() {
+            return Err(Error::new(
+                async_token.span,
+                "sections that can be skipped cannot be async",
+            ));
+        }
+        branch.section.asyncness = Some(async_token);
+    }
+    branch.section.attr_block = attr_block;
+    branch.section.vis = vis;
+    branch.section.unsafety = unsafety;
+    Ok(branch)
+}
 
-    let branch: Branch = input.parse()?;
-    let first_branch = DocumentedBranch { doc_block, branch };
+fn parse_documented_branch(input: ParseStream) -> Result<DocumentedBranch> {
+    let mut attr_block: AttributeBlock = input.parse()?;
+    let doc_block = DocumentationBlock::extract_from(&mut attr_block);
+    let branch = parse_fn_branch(attr_block, input)?;
+    Ok(DocumentedBranch { doc_block, branch })
+}
+
+fn try_parse_branches(
+    input: ParseStream,
+) -> Result<Box<(DocumentedBranch, Vec<DocumentedBranch>)>> {
+    let first_branch = parse_documented_branch(input)?;
     let mut other_branches = Vec::<DocumentedBranch>::new();
+    while !input.is_empty() {
+        other_branches.push(parse_documented_branch(input)?);
+    }
+    Ok(Box::new((first_branch, other_branches)))
+}
+
+// ⟨doc⟩ fn ⟨section1⟩; … ⟨doc⟩ fn ⟨sectionN⟩;
+fn try_parse_any_group(input: ParseStream) -> Result<Vec<(DocumentationBlock, Section)>> {
+    let any_token: kw::any = input.parse()?;
+    let mut members = Vec::<(DocumentationBlock, Section)>::new();
     while !input.is_empty() {
         let mut attr_block: AttributeBlock = input.parse()?;
         let doc_block = DocumentationBlock::extract_from(&mut attr_block);
-
         if !attr_block.is_empty() {
             return Err(Error::new(
                 input.span(),
                 "only document attributes are allowed here",
             ));
         }
-
         input.parse::<Token![fn]>()?;
+        let section: Section = input.parse()?;
+        if section.is_skippable() {
+            return Err(Error::new(
+                section.ident.span(),
+                "sections of an `any` group cannot be skipped",
+            ));
+        }
+        if let Some(alias) = &section.alias {
+            return Err(Error::new(
+                alias.as_token.span,
+                "sections of an `any` group cannot be named",
+            ));
+        }
+        if members.iter().any(|(_, s)| s.ident == section.ident) {
+            return Err(Error::new(
+                section.ident.span(),
+                format!(
+                    "section `{}` appears twice in the same `any` group",
+                    section.ident
+                ),
+            ));
+        }
+        input.parse::<Token![;]>()?;
+        members.push((doc_block, section));
+    }
+    if members.is_empty() {
+        return Err(Error::new(
+            any_token.span,
+            "an `any` group must contain at least one section",
+        ));
+    }
+    if MAX_ANY_GROUP_SIZE < members.len() {
+        return Err(Error::new(
+            any_token.span,
+            format!("an `any` group cannot contain more than {MAX_ANY_GROUP_SIZE} sections"),
+        ));
+    }
+    Ok(members)
+}
+
+// Expands the members of an `any` group into every possible order, each of them eventually
+// followed by `tail`.
+fn produce_permutations(
+    dot: Token![.],
+    members: &[(DocumentationBlock, Section)],
+    tail: &BranchTail,
+) -> BranchTail {
+    let mut branches = members.iter().enumerate().map(|(n, (doc_block, section))| {
+        let tail = if members.len() == 1 {
+            tail.clone()
+        } else {
+            let mut others = members.to_vec();
+            others.remove(n);
+            produce_permutations(dot, &others, tail)
+        };
+        let branch = Branch {
+            section: section.clone(),
+            tail,
+        };
+        DocumentedBranch {
+            doc_block: doc_block.clone(),
+            branch,
+        }
+    });
+    let first_branch = branches.next().expect("groups cannot be empty");
+    let other_branches = branches.collect();
+    BranchTail::Alternative {
+        body: None,
+        dot,
+        rest: Box::new((first_branch, other_branches)),
+    }
+}
+
+fn parse_tail_after_dot(dot: Token![.], input: ParseStream) -> Result<BranchTail> {
+    if let Ok(inner) = try_parse_brace(input) {
+        if inner.peek(kw::any) {
+            let members = try_parse_any_group(&inner)?;
+            if !input.peek(Token![.]) {
+                return Err(Error::new(
+                    input.span(),
+                    "an `any` group must be followed by another section",
+                ));
+            }
+            let next_dot: Token![.] = input.parse()?;
+            let tail = parse_tail_after_dot(next_dot, input)?;
+            Ok(produce_permutations(dot, &members, &tail))
+        } else {
+            let rest = try_parse_branches(&inner)?;
+            Ok(BranchTail::Alternative {
+                body: None,
+                dot,
+                rest,
+            })
+        }
+    } else {
         let branch: Branch = input.parse()?;
-        let branch = DocumentedBranch { doc_block, branch };
-        other_branches.push(branch);
+        let rest = DocumentedBranch {
+            doc_block: DocumentationBlock::new(),
+            branch,
+        };
+        let rest = Box::new((rest, Vec::new()));
+        Ok(BranchTail::Alternative {
+            body: None,
+            dot,
+            rest,
+        })
     }
-    Ok(Box::new((first_branch, other_branches)))
 }
 
 impl Parse for Branch {
@@ -87,21 +276,33 @@ impl Parse for Branch {
                 brace,
                 body,
             },
-            SectionTail::Dot(dot) => {
-                if let Ok(inner) = try_parse_brace(input) {
-                    let rest = try_parse_branches(&inner)?;
-                    BranchTail::Alternative { dot, rest }
-                } else {
-                    let branch: Branch = input.parse()?;
-                    let rest = DocumentedBranch {
-                        doc_block: DocumentationBlock::new(),
-                        branch,
-                    };
-                    let rest = Box::new((rest, Vec::new()));
-                    BranchTail::Alternative { dot, rest }
+            SectionTail::Declaration { output, semi_token } => {
+                BranchTail::Declaration { output, semi_token }
+            }
+            SectionTail::Dot(dot) => parse_tail_after_dot(dot, input)?,
+            SectionTail::Intermediate {
+                output,
+                brace,
+                body: stmts,
+                dot_token,
+            } => {
+                let mut tail = parse_tail_after_dot(dot_token, input)?;
+                if let BranchTail::Alternative { body, .. } = &mut tail {
+                    *body = Some(IntermediateBody {
+                        output,
+                        brace,
+                        stmts,
+                    });
                 }
+                tail
             }
         };
+        if let (true, Some(alias)) = (tail.is_final(), &section.section.alias) {
+            return Err(Error::new(
+                alias.as_token.span,
+                "only sections followed by other sections can be named",
+            ));
+        }
         Ok(Branch {
             section: section.section,
             tail,
@@ -116,11 +317,20 @@ impl ToTokens for DocumentedBranch {
     }
 }
 
+impl ToTokens for IntermediateBody {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.output.to_tokens(tokens);
+        self.brace
+            .surround(tokens, |tokens| self.stmts.to_tokens(tokens));
+    }
+}
+
 impl ToTokens for Branch {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         self.section.to_tokens(tokens);
         match &self.tail {
-            BranchTail::Alternative { dot, rest } => {
+            BranchTail::Alternative { body, dot, rest } => {
+                body.to_tokens(tokens);
                 dot.to_tokens(tokens);
                 if rest.1.is_empty() {
                     rest.0.to_tokens(tokens);
@@ -145,6 +355,143 @@ impl ToTokens for Branch {
                 output.to_tokens(tokens);
                 brace.surround(tokens, |tokens| body.to_tokens(tokens));
             }
+            BranchTail::Declaration { output, semi_token } => {
+                output.to_tokens(tokens);
+                semi_token.to_tokens(tokens);
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Branch;
+    use quote::quote;
+
+    #[test]
+    fn parse_any_group() {
+        let tokens = quote!(movie(name: &str).{ any fn released_in(year: usize); fn directed_by(director: &str); }.build() -> Movie { todo!() });
+
+        asserts::tokens_are_matching!(
+            Branch,
+            tokens,
+            "movie (name : & str) . {
+                fn released_in (year : usize) . directed_by (director : & str) . build () -> Movie { todo ! () }
+                fn directed_by (director : & str) . released_in (year : usize) . build () -> Movie { todo ! () }
+            }"
+        );
+
+        let tokens = quote!(movie().{ any fn released_in(year: usize); }.build() {});
+        asserts::tokens_are_matching!(
+            Branch,
+            tokens,
+            "movie () . released_in (year : usize) . build () { }"
+        );
+
+        let tokens = quote!(movie().{ any fn released_in(year: usize); fn released_in(year: u8); }.build() {});
+        asserts::tokens_are_not_matching!(
+            Branch,
+            tokens,
+            "section `released_in` appears twice in the same `any` group"
+        );
+
+        let tokens = quote!(movie().{ any fn released_in(year: usize)?; }.build() {});
+        asserts::tokens_are_not_matching!(
+            Branch,
+            tokens,
+            "sections of an `any` group cannot be skipped"
+        );
+
+        let tokens = quote!(movie().{ any fn released_in(year: usize); });
+        asserts::tokens_are_not_matching!(
+            Branch,
+            tokens,
+            "an `any` group must be followed by another section"
+        );
+
+        let tokens =
+            quote!(movie().{ any fn a(); fn b(); fn c(); fn d(); fn e(); fn f(); }.build() {});
+        asserts::tokens_are_not_matching!(
+            Branch,
+            tokens,
+            "an `any` group cannot contain more than 5 sections"
+        );
+    }
+
+    #[test]
+    fn parse_async_intermediate_sections() {
+        let tokens = quote!(connect(host: &str).async login(token: u32) { check(token).await; }.{
+            async fn fetch(id: u32) -> Item { todo!() }
+            fn ping() -> bool { true }
+        });
+
+        asserts::tokens_are_matching!(
+            Branch,
+            tokens,
+            "connect (host : & str) . async login (token : u32) { check (token) . await ; } . {
+                fn async fetch (id : u32) -> Item { todo ! () }
+                fn ping () -> bool { true }
+            }"
+        );
+
+        let tokens = quote!(connect().async login(token: u32)?.fetch() {});
+        asserts::tokens_are_not_matching!(
+            Branch,
+            tokens,
+            "sections that can be skipped cannot be async"
+        );
+    }
+
+    #[test]
+    fn parse_named_sections() {
+        let tokens = quote!(request(url: Uri) as Request.from(agent: &str) as RequestFrom.{
+            fn send() -> Response { todo!() }
+        });
+
+        asserts::tokens_are_matching!(
+            Branch,
+            tokens,
+            "request (url : Uri) as Request . from (agent : & str) as RequestFrom . send () -> Response { todo ! () }"
+        );
+
+        let tokens = quote!(request(url: Uri).send() as Sent -> Response { todo!() });
+        asserts::tokens_are_not_matching!(
+            Branch,
+            tokens,
+            "only sections followed by other sections can be named"
+        );
+
+        let tokens = quote!(movie().{ any fn released_in(year: usize) as Released; }.build() {});
+        asserts::tokens_are_not_matching!(
+            Branch,
+            tokens,
+            "sections of an `any` group cannot be named"
+        );
+    }
+
+    #[test]
+    fn parse_qualified_alternatives() {
+        let tokens = quote!(request(url: Uri).{
+            #[cfg(feature = "get")]
+            #[must_use]
+            pub(crate) async fn get() -> Response { todo!() }
+            unsafe fn raw(bytes: *const u8) -> Response { todo!() }
+        });
+
+        asserts::tokens_are_matching!(
+            Branch,
+            tokens,
+            "request (url : Uri) . {
+                fn # [cfg (feature = \"get\")] # [must_use] pub (crate) async get () -> Response { todo ! () }
+                fn unsafe raw (bytes : * const u8) -> Response { todo ! () }
+            }"
+        );
+
+        let tokens = quote!(request(url: Uri).{ pub(super) fn get() -> Response { todo!() } });
+        asserts::tokens_are_not_matching!(
+            Branch,
+            tokens,
+            "alternatives can only be `pub` or `pub(crate)`"
+        );
+    }
+}
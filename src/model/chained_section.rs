@@ -1,18 +1,29 @@
 use proc_macro2::TokenStream;
 use quote::ToTokens;
 use syn::parse::{Parse, ParseBuffer, ParseStream};
+use syn::spanned::Spanned;
 use syn::token::Brace;
-use syn::{braced, Result, ReturnType, Token};
+use syn::{braced, Error, GenericArgument, PathArguments, Result, ReturnType, Token, Type};
 
 use super::section::Section;
 
 pub enum SectionTail {
     Dot(Token![.]),
+    Intermediate {
+        output: ReturnType,
+        brace: Brace,
+        body: TokenStream,
+        dot_token: Token![.],
+    },
     Content {
         output: ReturnType,
         brace: Brace,
         body: TokenStream,
     },
+    Declaration {
+        output: ReturnType,
+        semi_token: Token![;],
+    },
 }
 
 pub struct ChainedSection {
@@ -20,10 +31,98 @@ pub struct ChainedSection {
     pub tail: SectionTail,
 }
 
+// Sections that can be skipped cannot be followed by a body and must not introduce
+// generic parameters or bounds, which would be left undetermined or unchecked when skipping.
+fn check_skippable_section(section: &Section, input: ParseStream) -> Result<()> {
+    let span = match (&section.question_token, &section.repetition) {
+        (Some(question_token), Some(_)) => {
+            return Err(Error::new(
+                question_token.span,
+                "a section cannot be both optional and repeated",
+            ));
+        }
+        (Some(question_token), None) if section.is_defaulted() => {
+            return Err(Error::new(
+                question_token.span,
+                "an optional section cannot declare default values",
+            ));
+        }
+        (None, Some(repetition)) if section.is_defaulted() => {
+            return Err(Error::new(
+                repetition.star_token.span,
+                "a repeated section cannot declare default values",
+            ));
+        }
+        (Some(question_token), None) => question_token.span,
+        (None, Some(repetition)) => {
+            if let Some(max) = &repetition.max {
+                if max.value.base10_parse::<usize>()? == 0 {
+                    return Err(Error::new(
+                        max.value.span(),
+                        "the maximum number of repetitions must be positive",
+                    ));
+                }
+            }
+            repetition.star_token.span
+        }
+        (None, None) if section.is_defaulted() => section.paren_token.span.join(),
+        (None, None) => return Ok(()),
+    };
+    if let Some(async_token) = &section.asyncness {
+        return Err(Error::new(
+            async_token.span,
+            "sections that can be skipped cannot be async",
+        ));
+    }
+    if !section.generics.params.is_empty() {
+        return Err(Error::new(
+            section.generics.span(),
+            "sections that can be skipped cannot declare generic parameters",
+        ));
+    }
+    if input.peek(Token![where]) {
+        return Err(input.error("sections that can be skipped cannot declare where clauses"));
+    }
+    if !input.peek(Token![.]) {
+        return Err(Error::new(
+            span,
+            "a section that can be skipped must be followed by another section",
+        ));
+    }
+    Ok(())
+}
+
+// Intermediate sections can only fail, returning the next step wrapped in `Result<_, E>` or
+// `Option<_>`.
+fn check_intermediate_output(output: &ReturnType) -> Result<()> {
+    let ReturnType::Type(_, ty) = output else {
+        return Ok(());
+    };
+    if let Type::Path(path) = ty.as_ref() {
+        if let Some(segment) = path.path.segments.last() {
+            if let PathArguments::AngleBracketed(args) = &segment.arguments {
+                let wraps_next_step = matches!(
+                    args.args.first(),
+                    Some(GenericArgument::Type(Type::Infer(_)))
+                );
+                if (segment.ident == "Result" || segment.ident == "Option") && wraps_next_step {
+                    return Ok(());
+                }
+            }
+        }
+    }
+    Err(Error::new(
+        ty.span(),
+        "an intermediate section can only return `Result<_, E>` or `Option<_>`, where `_` stands for the next step",
+    ))
+}
+
 impl Parse for ChainedSection {
     fn parse(input: ParseStream) -> Result<Self> {
         let mut section: Section = input.parse()?;
 
+        check_skippable_section(&section, input)?;
+
         let tail = if input.peek(Token![.]) {
             let dot_token: Token![.] = input.parse()?;
             SectionTail::Dot(dot_token)
@@ -31,11 +130,43 @@ impl Parse for ChainedSection {
             let output: ReturnType = input.parse()?;
             section.generics.where_clause = input.parse()?;
 
+            // Where clauses of sections without a body accumulate down the method chain.
+            if matches!(output, ReturnType::Default) && input.peek(Token![.]) {
+                let dot_token: Token![.] = input.parse()?;
+                return Ok(ChainedSection {
+                    section,
+                    tail: SectionTail::Dot(dot_token),
+                });
+            }
+
+            if input.peek(Token![;]) {
+                let semi_token: Token![;] = input.parse()?;
+                return Ok(ChainedSection {
+                    section,
+                    tail: SectionTail::Declaration { output, semi_token },
+                });
+            }
+
             let content: ParseBuffer<'_>;
             let brace: Brace = braced!(content in input);
 
             let body: TokenStream = content.parse()?;
 
+            // A body followed by another section is run before continuing the method chain.
+            if input.peek(Token![.]) {
+                check_intermediate_output(&output)?;
+                let dot_token: Token![.] = input.parse()?;
+                return Ok(ChainedSection {
+                    section,
+                    tail: SectionTail::Intermediate {
+                        output,
+                        brace,
+                        body,
+                        dot_token,
+                    },
+                });
+            }
+
             SectionTail::Content {
                 output,
                 brace,
@@ -50,6 +181,16 @@ impl ToTokens for SectionTail {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         match self {
             Self::Dot(dot_token) => dot_token.to_tokens(tokens),
+            Self::Intermediate {
+                output,
+                brace,
+                body,
+                dot_token,
+            } => {
+                output.to_tokens(tokens);
+                brace.surround(tokens, |tokens| body.to_tokens(tokens));
+                dot_token.to_tokens(tokens);
+            }
             Self::Content {
                 output,
                 brace,
@@ -58,6 +199,10 @@ impl ToTokens for SectionTail {
                 output.to_tokens(tokens);
                 brace.surround(tokens, |tokens| body.to_tokens(tokens));
             }
+            Self::Declaration { output, semi_token } => {
+                output.to_tokens(tokens);
+                semi_token.to_tokens(tokens);
+            }
         }
     }
 }
@@ -108,4 +253,129 @@ mod tests {
 
         asserts::tokens_are_not_matching!(ChainedSection, tokens, "expected curly braces");
     }
+
+    #[test]
+    fn parse_intermediate_chained_section() {
+        let tokens = quote!(released_in(year: usize) { check(year); }.);
+
+        asserts::tokens_are_matching!(
+            ChainedSection,
+            tokens,
+            "released_in (year : usize) { check (year) ; } ."
+        );
+
+        let tokens = quote!(released_in(year: usize) -> Result<_, MovieError> { check(year)?; }.);
+
+        asserts::tokens_are_matching!(
+            ChainedSection,
+            tokens,
+            "released_in (year : usize) -> Result < _ , MovieError > { check (year) ? ; } ."
+        );
+
+        let tokens = quote!(released_in(year: usize) -> usize { year }.);
+
+        asserts::tokens_are_not_matching!(
+            ChainedSection,
+            tokens,
+            "an intermediate section can only return `Result<_, E>` or `Option<_>`, where `_` stands for the next step"
+        );
+    }
+
+    #[test]
+    fn parse_skippable_chained_section() {
+        let tokens = quote!(released_in(year: usize)?.);
+
+        asserts::tokens_are_matching!(ChainedSection, tokens, "released_in (year : usize) ? .");
+
+        let tokens = quote!(released_in(year: usize)? { 5 });
+
+        asserts::tokens_are_not_matching!(
+            ChainedSection,
+            tokens,
+            "a section that can be skipped must be followed by another section"
+        );
+
+        let tokens = quote!(released_in<T>(year: T)?.);
+
+        asserts::tokens_are_not_matching!(
+            ChainedSection,
+            tokens,
+            "sections that can be skipped cannot declare generic parameters"
+        );
+
+        let tokens = quote!(at_most(n: usize = 1).);
+
+        asserts::tokens_are_matching!(ChainedSection, tokens, "at_most (n : usize = 1) .");
+
+        let tokens = quote!(at_most(n: usize = 1) -> usize { n });
+
+        asserts::tokens_are_not_matching!(
+            ChainedSection,
+            tokens,
+            "a section that can be skipped must be followed by another section"
+        );
+
+        let tokens = quote!(at_most(n: usize = 1)?.);
+
+        asserts::tokens_are_not_matching!(
+            ChainedSection,
+            tokens,
+            "an optional section cannot declare default values"
+        );
+
+        let tokens = quote!(header(name: String, value: String)*.);
+
+        asserts::tokens_are_matching!(
+            ChainedSection,
+            tokens,
+            "header (name : String , value : String) * ."
+        );
+
+        let tokens = quote!(header(name: String, value: String)*[..=4].);
+
+        asserts::tokens_are_matching!(
+            ChainedSection,
+            tokens,
+            "header (name : String , value : String) * [..= 4] ."
+        );
+
+        let tokens = quote!(header(name: String, value: String)*[..=0].);
+
+        asserts::tokens_are_not_matching!(
+            ChainedSection,
+            tokens,
+            "the maximum number of repetitions must be positive"
+        );
+
+        let tokens = quote!(header(name: String)? *.);
+
+        asserts::tokens_are_not_matching!(
+            ChainedSection,
+            tokens,
+            "a section cannot be both optional and repeated"
+        );
+
+        let tokens = quote!(released_in(year: T)? where T: Copy .);
+
+        asserts::tokens_are_not_matching!(
+            ChainedSection,
+            tokens,
+            "sections that can be skipped cannot declare where clauses"
+        );
+    }
+
+    #[test]
+    fn parse_bounded_chained_section() {
+        let tokens = quote!(released_in<T>(year: T) where T: Into<usize> .);
+
+        asserts::tokens_are_matching!(
+            ChainedSection,
+            tokens,
+            "released_in < T > (year : T) where T : Into < usize > ."
+        );
+
+        let tokens = quote!(released_in<T>(year: T) -> usize where T: Into<usize> .);
+
+        asserts::tokens_are_not_matching!(ChainedSection, tokens, "expected curly braces");
+    }
 }
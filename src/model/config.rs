@@ -0,0 +1,196 @@
+use proc_macro2::Span;
+use syn::{meta::ParseNestedMeta, Attribute, Error, Ident, LitStr, Result, Visibility};
+
+use super::options::{Layout, ASSEMBLIST_ATTRIBUTE_NAME};
+
+const DEFAULT_RECEIVER_NAME: &str = "self_";
+const DEFAULT_DOC_SEPARATOR: &str = "---";
+
+// How intermediate methods are inlined.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Inlining {
+    Hint,
+    Always,
+    Never,
+    Off,
+}
+
+// Configuration of a whole `assemblist!` invocation, declared through `#![assemblist(…)]` inner
+// attributes at its top.
+#[derive(Clone)]
+pub struct GenerationConfig {
+    pub inline: Inlining,
+    pub receiver: Ident,
+    pub doc_separator: String,
+    pub layout: Option<Layout>,
+    // Visibility of the modules of the nested layout, instead of the one of each method chain.
+    pub module_visibility: Option<Visibility>,
+}
+
+impl Default for GenerationConfig {
+    fn default() -> Self {
+        Self {
+            inline: Inlining::Hint,
+            receiver: Ident::new(DEFAULT_RECEIVER_NAME, Span::call_site()),
+            doc_separator: DEFAULT_DOC_SEPARATOR.to_string(),
+            layout: None,
+            module_visibility: None,
+        }
+    }
+}
+
+impl GenerationConfig {
+    pub fn extract_from(attrs: Vec<Attribute>) -> Result<Self> {
+        let mut config = Self::default();
+        for attr in attrs {
+            if !attr.path().is_ident(ASSEMBLIST_ATTRIBUTE_NAME) {
+                return Err(Error::new_spanned(
+                    attr,
+                    "only `assemblist` inner attributes are allowed here",
+                ));
+            }
+            config.parse_attribute(&attr)?;
+        }
+        Ok(config)
+    }
+
+    fn parse_attribute(&mut self, attr: &Attribute) -> Result<()> {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("inline") {
+                self.parse_inline(meta)
+            } else if meta.path.is_ident("receiver") {
+                self.parse_receiver(meta)
+            } else if meta.path.is_ident("doc_separator") {
+                self.parse_doc_separator(meta)
+            } else if meta.path.is_ident("layout") {
+                let value: LitStr = meta.value()?.parse()?;
+                self.layout = Some(Layout::parse_lit(&value)?);
+                Ok(())
+            } else if meta.path.is_ident("module_visibility") {
+                self.parse_module_visibility(meta)
+            } else {
+                Err(meta.error("unsupported `assemblist` configuration"))
+            }
+        })
+    }
+
+    fn parse_inline(&mut self, meta: ParseNestedMeta) -> Result<()> {
+        let value: LitStr = meta.value()?.parse()?;
+        self.inline = match value.value().as_str() {
+            "hint" => Inlining::Hint,
+            "always" => Inlining::Always,
+            "never" => Inlining::Never,
+            "off" => Inlining::Off,
+            _ => {
+                let message =
+                    "expected `\"hint\"`, `\"always\"`, `\"never\"` or `\"off\"` inlining";
+                return Err(Error::new(value.span(), message));
+            }
+        };
+        Ok(())
+    }
+
+    fn parse_receiver(&mut self, meta: ParseNestedMeta) -> Result<()> {
+        let value: LitStr = meta.value()?.parse()?;
+        match syn::parse_str::<Ident>(&value.value()) {
+            Ok(ident) => {
+                self.receiver = Ident::new(&ident.to_string(), value.span());
+                Ok(())
+            }
+            Err(_) => {
+                let message = format!("`{}` is not a valid receiver name", value.value());
+                Err(Error::new(value.span(), message))
+            }
+        }
+    }
+
+    fn parse_module_visibility(&mut self, meta: ParseNestedMeta) -> Result<()> {
+        let value: LitStr = meta.value()?.parse()?;
+        match value.parse::<Visibility>() {
+            Ok(vis) => {
+                self.module_visibility = Some(vis);
+                Ok(())
+            }
+            Err(_) => {
+                let message = format!("`{}` is not a valid visibility", value.value());
+                Err(Error::new(value.span(), message))
+            }
+        }
+    }
+
+    fn parse_doc_separator(&mut self, meta: ParseNestedMeta) -> Result<()> {
+        let value: LitStr = meta.value()?.parse()?;
+        if value.value().trim().is_empty() {
+            return Err(Error::new(
+                value.span(),
+                "documentation separators cannot be blank",
+            ));
+        }
+        self.doc_separator = value.value();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GenerationConfig, Inlining, Layout};
+    use crate::model::tree::Tree;
+    use quote::quote;
+    use syn::{parse::Parser, Attribute, Visibility};
+
+    #[test]
+    fn extract_config() {
+        let tokens = quote!(
+            #![assemblist(inline = "always", receiver = "this")]
+            #![assemblist(doc_separator = "===", layout = "flat")]
+            #![assemblist(module_visibility = "pub(crate)")]
+        );
+        let attrs = Attribute::parse_inner.parse2(tokens).unwrap();
+        let config = GenerationConfig::extract_from(attrs).unwrap();
+
+        assert!(config.inline == Inlining::Always);
+        assert_eq!("this", config.receiver.to_string());
+        assert_eq!("===", config.doc_separator);
+        assert!(config.layout == Some(Layout::Flat));
+        assert!(matches!(
+            config.module_visibility,
+            Some(Visibility::Restricted(_))
+        ));
+    }
+
+    #[test]
+    fn reject_unknown_config() {
+        let tokens = quote!(#![assemblist(inline = "sometimes")] fn first().second() {});
+        asserts::tokens_are_not_matching!(
+            Tree,
+            tokens,
+            "expected `\"hint\"`, `\"always\"`, `\"never\"` or `\"off\"` inlining"
+        );
+
+        let tokens = quote!(#![assemblist(receiver = "self")] fn first().second() {});
+        asserts::tokens_are_not_matching!(Tree, tokens, "`self` is not a valid receiver name");
+
+        let tokens = quote!(#![assemblist(doc_separator = " ")] fn first().second() {});
+        asserts::tokens_are_not_matching!(Tree, tokens, "documentation separators cannot be blank");
+
+        let tokens = quote!(#![assemblist(layout = "inline")] fn first().second() {});
+        asserts::tokens_are_not_matching!(
+            Tree,
+            tokens,
+            "expected `\"nested\"` or `\"flat\"` layout"
+        );
+
+        let tokens = quote!(#![assemblist(module_visibility = "pub(all)")] fn first().second() {});
+        asserts::tokens_are_not_matching!(Tree, tokens, "`pub(all)` is not a valid visibility");
+
+        let tokens = quote!(#![assemblist(accessors)] fn first().second() {});
+        asserts::tokens_are_not_matching!(Tree, tokens, "unsupported `assemblist` configuration");
+
+        let tokens = quote!(#![allow(unused)] fn first().second() {});
+        asserts::tokens_are_not_matching!(
+            Tree,
+            tokens,
+            "only `assemblist` inner attributes are allowed here"
+        );
+    }
+}
@@ -1,6 +1,8 @@
 pub mod attribute;
 pub mod branch;
 pub mod chained_section;
+pub mod config;
+pub mod options;
 pub mod prelude;
 pub mod section;
 pub mod tree;
@@ -0,0 +1,298 @@
+use syn::{meta::ParseNestedMeta, Attribute, Error, Ident, LitStr, Result};
+
+use super::attribute::AttributeBlock;
+
+// Name of the attributes holding the options of method chains and the configuration of whole
+// invocations.
+pub const ASSEMBLIST_ATTRIBUTE_NAME: &str = "assemblist";
+const DERIVABLE_TRAITS: [&str; 8] = [
+    "Clone",
+    "Copy",
+    "Debug",
+    "PartialEq",
+    "Eq",
+    "Hash",
+    "PartialOrd",
+    "Ord",
+];
+
+const NAMING_PLACEHOLDER: &str = "{}";
+
+// Where intermediate types are declared: each one in its own module nested in the module of the
+// previous section, or all of them side by side.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Layout {
+    Nested,
+    Flat,
+}
+
+impl Layout {
+    pub fn parse_lit(value: &LitStr) -> Result<Self> {
+        match value.value().as_str() {
+            "nested" => Ok(Layout::Nested),
+            "flat" => Ok(Layout::Flat),
+            _ => {
+                let message = "expected `\"nested\"` or `\"flat\"` layout";
+                Err(Error::new(value.span(), message))
+            }
+        }
+    }
+}
+
+// Options of a method chain, declared through `#[assemblist(…)]` attributes.
+#[derive(Clone, Default, PartialEq)]
+pub struct ChainOptions {
+    pub derives: Vec<Ident>,
+    pub accessors: bool,
+    pub by_ref: bool,
+    pub layout: Option<Layout>,
+    pub naming: Option<String>,
+}
+
+impl ChainOptions {
+    pub fn extract_from(attr_block: &mut AttributeBlock) -> Result<Self> {
+        let mut options = Self::default();
+        for attr in attr_block.take_matching(ASSEMBLIST_ATTRIBUTE_NAME) {
+            options.parse_attribute(&attr)?;
+        }
+        Ok(options)
+    }
+
+    // A naming scheme implies the flat layout.
+    pub fn is_flat(&self) -> bool {
+        match self.layout {
+            Some(layout) => layout == Layout::Flat,
+            None => self.naming.is_some(),
+        }
+    }
+
+    // The name of a flat intermediate type, given the names of its sections in camel case.
+    pub fn flat_type_name(&self, sections: &str) -> String {
+        match &self.naming {
+            Some(naming) => naming.replace(NAMING_PLACEHOLDER, sections),
+            None => sections.to_string(),
+        }
+    }
+
+    // Options declared on an `impl` or `trait` block apply to all its method chains. A layout or
+    // naming scheme chosen by a method chain takes precedence over the one of its block.
+    pub fn inherit_from(&mut self, parent: &ChainOptions) {
+        self.accessors |= parent.accessors;
+        self.by_ref |= parent.by_ref;
+        if self.naming.is_none() {
+            self.layout = self.layout.or(parent.layout);
+        }
+        if self.layout != Some(Layout::Nested) {
+            self.naming = self.naming.take().or_else(|| parent.naming.clone());
+        }
+        for derive in &parent.derives {
+            if !self.derives.contains(derive) {
+                self.derives.push(derive.clone());
+            }
+        }
+    }
+
+    // The layout configured for a whole invocation applies to method chains that do not choose
+    // theirs, possibly through a naming scheme.
+    pub fn inherit_layout(&mut self, layout: Option<Layout>) {
+        if self.naming.is_none() {
+            self.layout = self.layout.or(layout);
+        }
+    }
+
+    fn parse_attribute(&mut self, attr: &Attribute) -> Result<()> {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("derive") {
+                meta.parse_nested_meta(|meta| self.parse_derive(meta))
+            } else if meta.path.is_ident("accessors") {
+                self.accessors = true;
+                Ok(())
+            } else if meta.path.is_ident("by_ref") {
+                self.by_ref = true;
+                Ok(())
+            } else if meta.path.is_ident("layout") {
+                self.parse_layout(meta)
+            } else if meta.path.is_ident("naming") {
+                self.parse_naming(meta)
+            } else {
+                Err(meta.error("unsupported `assemblist` option"))
+            }
+        })
+    }
+
+    fn parse_layout(&mut self, meta: ParseNestedMeta) -> Result<()> {
+        let value: LitStr = meta.value()?.parse()?;
+        let layout = Layout::parse_lit(&value)?;
+        if layout == Layout::Nested && self.naming.is_some() {
+            return Err(Error::new(
+                value.span(),
+                "naming schemes only apply to the flat layout",
+            ));
+        }
+        self.layout = Some(layout);
+        Ok(())
+    }
+
+    fn parse_naming(&mut self, meta: ParseNestedMeta) -> Result<()> {
+        let value: LitStr = meta.value()?.parse()?;
+        let naming = value.value();
+        if naming.matches(NAMING_PLACEHOLDER).count() != 1 {
+            let message = "naming schemes must contain `{}` once, standing for the section names";
+            return Err(Error::new(value.span(), message));
+        }
+        if syn::parse_str::<Ident>(&naming.replace(NAMING_PLACEHOLDER, "Section")).is_err() {
+            let message = format!("naming scheme `{naming}` does not produce valid type names");
+            return Err(Error::new(value.span(), message));
+        }
+        if self.layout == Some(Layout::Nested) {
+            return Err(Error::new(
+                value.span(),
+                "naming schemes only apply to the flat layout",
+            ));
+        }
+        self.naming = Some(naming);
+        Ok(())
+    }
+
+    fn parse_derive(&mut self, meta: ParseNestedMeta) -> Result<()> {
+        let ident = meta.path.require_ident()?;
+        if !DERIVABLE_TRAITS.iter().any(|name| ident == name) {
+            let expected = DERIVABLE_TRAITS
+                .iter()
+                .map(|name| format!("`{name}`"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let message = format!("`{ident}` cannot be derived, expected one of {expected}");
+            return Err(Error::new(ident.span(), message));
+        }
+        if !self.derives.contains(ident) {
+            self.derives.push(ident.clone());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ChainOptions;
+    use crate::model::{
+        attribute::AttributeBlock,
+        tree::Tree,
+        trunk::{Trunk, TrunkAlternative},
+    };
+    use quote::{quote, ToTokens};
+
+    #[test]
+    fn extract_options() {
+        let tokens = quote!(
+            #[inline]
+            #[assemblist(derive(Clone, Debug))]
+            #[assemblist(derive(Clone, PartialEq), accessors)]
+            #[assemblist(by_ref, naming = "{}Builder")]
+        );
+        let mut attr_block = asserts::tokens_are_parsable_as::<AttributeBlock>(tokens);
+        let options = ChainOptions::extract_from(&mut attr_block).unwrap();
+
+        let derives = options
+            .derives
+            .iter()
+            .map(|ident| ident.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["Clone", "Debug", "PartialEq"], derives);
+        assert!(options.accessors);
+        assert!(options.by_ref);
+        assert!(options.is_flat());
+        assert_eq!("FirstSecondBuilder", options.flat_type_name("FirstSecond"));
+        asserts::equivalent!(
+            attr_block.to_token_stream().to_string().as_str(),
+            "# [inline]"
+        );
+    }
+
+    #[test]
+    fn inherit_block_options() {
+        let tokens = quote!(
+            #[assemblist(layout = "nested", accessors)]
+            impl Intro {
+                #[assemblist(naming = "{}Builder")]
+                fn first().second() {}
+                fn third().fourth() {}
+            }
+        );
+        let trunk = asserts::tokens_are_parsable_as::<Trunk>(tokens);
+        let TrunkAlternative::Impl { fn_trunks, .. } = trunk.alternative else {
+            panic!("Should be an implementation");
+        };
+        let options = &fn_trunks[0].prelude.options;
+        assert!(options.accessors);
+        assert!(options.is_flat());
+        assert_eq!("FirstSecondBuilder", options.flat_type_name("FirstSecond"));
+        assert!(!fn_trunks[1].prelude.options.is_flat());
+
+        let tokens = quote!(
+            #[assemblist(naming = "{}Builder")]
+            impl Intro {
+                #[assemblist(layout = "nested")]
+                fn first().second() {}
+                fn third().fourth() {}
+            }
+        );
+        let trunk = asserts::tokens_are_parsable_as::<Trunk>(tokens);
+        let TrunkAlternative::Impl { fn_trunks, .. } = trunk.alternative else {
+            panic!("Should be an implementation");
+        };
+        assert!(!fn_trunks[0].prelude.options.is_flat());
+        assert!(fn_trunks[0].prelude.options.naming.is_none());
+        assert!(fn_trunks[1].prelude.options.is_flat());
+
+        let tokens = quote!(
+            #![assemblist(layout = "nested")]
+            #[assemblist(naming = "{}Builder")]
+            fn first().second() {}
+        );
+        let tree = asserts::tokens_are_parsable_as::<Tree>(tokens);
+        assert!(tree.roots[0].prelude.options.is_flat());
+    }
+
+    #[test]
+    fn reject_unknown_options() {
+        let tokens = quote!(#[assemblist(derive(Default))] fn first().second() {});
+        asserts::tokens_are_not_matching!(
+            Trunk,
+            tokens,
+            "`Default` cannot be derived, expected one of `Clone`, `Copy`, `Debug`, `PartialEq`, `Eq`, `Hash`, `PartialOrd`, `Ord`"
+        );
+
+        let tokens = quote!(#[assemblist(unknown)] fn first().second() {});
+        asserts::tokens_are_not_matching!(Trunk, tokens, "unsupported `assemblist` option");
+
+        let tokens = quote!(#[assemblist(layout = "deep")] fn first().second() {});
+        asserts::tokens_are_not_matching!(
+            Trunk,
+            tokens,
+            "expected `\"nested\"` or `\"flat\"` layout"
+        );
+
+        let tokens = quote!(#[assemblist(naming = "Builder")] fn first().second() {});
+        asserts::tokens_are_not_matching!(
+            Trunk,
+            tokens,
+            "naming schemes must contain `{}` once, standing for the section names"
+        );
+
+        let tokens = quote!(#[assemblist(naming = "{}-Builder")] fn first().second() {});
+        asserts::tokens_are_not_matching!(
+            Trunk,
+            tokens,
+            "naming scheme `{}-Builder` does not produce valid type names"
+        );
+
+        let tokens =
+            quote!(#[assemblist(layout = "nested", naming = "{}Builder")] fn first().second() {});
+        asserts::tokens_are_not_matching!(
+            Trunk,
+            tokens,
+            "naming schemes only apply to the flat layout"
+        );
+    }
+}
@@ -6,14 +6,18 @@ use syn::{
 };
 
 use super::attribute::{AttributeBlock, DocumentationBlock};
+use super::options::ChainOptions;
 
+#[derive(Clone)]
 pub struct Prelude {
+    pub options: ChainOptions,
     pub attr_block: AttributeBlock,
     pub vis: Visibility,
     pub asyncness: Option<Token![async]>,
 }
 
 pub struct Intro {
+    pub options: ChainOptions,
     pub doc_block: DocumentationBlock,
     pub attr_block: AttributeBlock,
     pub vis: Visibility,
@@ -23,10 +27,12 @@ pub struct Intro {
 impl Parse for Intro {
     fn parse(input: ParseStream) -> Result<Self> {
         let mut attr_block: AttributeBlock = input.parse()?;
+        let options = ChainOptions::extract_from(&mut attr_block)?;
         let doc_block = DocumentationBlock::extract_from(&mut attr_block);
         let vis: Visibility = input.parse()?;
         let asyncness: Option<Token![async]> = input.parse()?;
         Ok(Self {
+            options,
             doc_block,
             attr_block,
             vis,
@@ -46,6 +52,7 @@ impl ToTokens for Prelude {
 impl Intro {
     pub fn split(self) -> (Prelude, DocumentationBlock) {
         let prelude = Prelude {
+            options: self.options,
             attr_block: self.attr_block,
             vis: self.vis,
             asyncness: self.asyncness,
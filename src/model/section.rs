@@ -3,56 +3,219 @@ use quote::ToTokens;
 use syn::parse::discouraged::Speculative;
 use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
-use syn::token::{Comma, Paren};
+use syn::spanned::Spanned;
+use syn::token::{Bracket, Comma, Paren};
 use syn::{
-    parenthesized, Attribute, Error, FnArg, Generics, Ident, Pat, PatType, Receiver, Result, Token,
+    bracketed, parenthesized, Attribute, Error, Expr, FnArg, Generics, Ident, LitInt, Pat, PatType,
+    Receiver, Result, Token, Visibility,
 };
 
+use super::attribute::AttributeBlock;
+
+#[derive(Clone)]
+pub struct ArgDefault {
+    pub eq_token: Token![=],
+    pub expr: Expr,
+}
+
+#[derive(Clone)]
+pub struct SectionArg {
+    pub fn_arg: FnArg,
+    pub default: Option<ArgDefault>,
+}
+
+#[derive(Clone)]
+pub struct RepetitionMax {
+    pub bracket_token: Bracket,
+    pub dot2_eq_token: Token![..=],
+    pub value: LitInt,
+}
+
+#[derive(Clone)]
+pub struct Repetition {
+    pub star_token: Token![*],
+    pub max: Option<RepetitionMax>,
+}
+
+// Explicit name of the type returned by a section, as in `.section(…) as TypeName`.
+#[derive(Clone)]
+pub struct SectionAlias {
+    pub as_token: Token![as],
+    pub ident: Ident,
+}
+
+// Attributes, visibility and `unsafe` qualifier are only available to the sections of
+// alternatives, as in `.{ #[must_use] pub(crate) unsafe fn section(…) … }`.
+#[derive(Clone)]
 pub struct Section {
+    pub attr_block: AttributeBlock,
+    pub vis: Visibility,
+    pub asyncness: Option<Token![async]>,
+    pub unsafety: Option<Token![unsafe]>,
     pub ident: Ident,
     pub generics: Generics,
     pub paren_token: Paren,
-    pub inputs: Punctuated<FnArg, Comma>,
+    pub inputs: Punctuated<SectionArg, Comma>,
+    pub question_token: Option<Token![?]>,
+    pub repetition: Option<Repetition>,
+    pub alias: Option<SectionAlias>,
 }
 
 impl Parse for Section {
     fn parse(input: ParseStream) -> Result<Self> {
+        let asyncness: Option<Token![async]> = input.parse()?;
         let ident: Ident = input.parse()?;
         let generics: Generics = input.parse()?;
 
         let content;
         let paren_token = parenthesized!(content in input);
         let inputs = parse_fn_args(&content)?;
+        let question_token: Option<Token![?]> = input.parse()?;
+        let repetition = if input.peek(Token![*]) {
+            let star_token: Token![*] = input.parse()?;
+            let max = if input.peek(Bracket) {
+                let content;
+                let bracket_token = bracketed!(content in input);
+                let dot2_eq_token: Token![..=] = content.parse()?;
+                let value: LitInt = content.parse()?;
+                Some(RepetitionMax {
+                    bracket_token,
+                    dot2_eq_token,
+                    value,
+                })
+            } else {
+                None
+            };
+            Some(Repetition { star_token, max })
+        } else {
+            None
+        };
+        let alias = if input.peek(Token![as]) {
+            let as_token: Token![as] = input.parse()?;
+            let ident: Ident = input.parse()?;
+            Some(SectionAlias { as_token, ident })
+        } else {
+            None
+        };
 
         Ok(Section {
+            attr_block: AttributeBlock::new(),
+            vis: Visibility::Inherited,
+            asyncness,
+            unsafety: None,
             ident,
             generics,
             paren_token,
             inputs,
+            question_token,
+            repetition,
+            alias,
         })
     }
 }
 
 impl ToTokens for Section {
     fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.attr_block.to_tokens(tokens);
+        self.vis.to_tokens(tokens);
+        self.asyncness.to_tokens(tokens);
+        self.unsafety.to_tokens(tokens);
         self.ident.to_tokens(tokens);
         self.generics.to_tokens(tokens);
         self.paren_token
             .surround(tokens, |tokens| self.inputs.to_tokens(tokens));
+        self.question_token.to_tokens(tokens);
+        if let Some(repetition) = &self.repetition {
+            repetition.star_token.to_tokens(tokens);
+            if let Some(max) = &repetition.max {
+                max.bracket_token.surround(tokens, |tokens| {
+                    max.dot2_eq_token.to_tokens(tokens);
+                    max.value.to_tokens(tokens);
+                });
+            }
+        }
+        if let Some(alias) = &self.alias {
+            alias.as_token.to_tokens(tokens);
+            alias.ident.to_tokens(tokens);
+        }
         self.generics.where_clause.to_tokens(tokens);
     }
 }
 
-fn parse_fn_args(input: ParseStream) -> Result<Punctuated<FnArg, Token![,]>> {
-    let mut args = Punctuated::new();
+impl ToTokens for SectionArg {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.fn_arg.to_tokens(tokens);
+        if let Some(default) = &self.default {
+            default.eq_token.to_tokens(tokens);
+            default.expr.to_tokens(tokens);
+        }
+    }
+}
+
+impl Section {
+    pub fn is_optional(&self) -> bool {
+        self.question_token.is_some()
+    }
+
+    // Whether all arguments come with a default value so that the section can be skipped.
+    pub fn is_defaulted(&self) -> bool {
+        !self.inputs.is_empty() && self.inputs.iter().all(|arg| arg.default.is_some())
+    }
+
+    pub fn is_repeated(&self) -> bool {
+        self.repetition.is_some()
+    }
+
+    pub fn max_repetitions(&self) -> Option<usize> {
+        let max = self.repetition.as_ref()?.max.as_ref()?;
+        max.value.base10_parse::<usize>().ok()
+    }
+
+    pub fn is_skippable(&self) -> bool {
+        self.is_optional() || self.is_defaulted() || self.is_repeated()
+    }
+}
+
+impl Parse for SectionArg {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let fn_arg = parse_fn_arg(input, attrs)?;
+
+        let default = if input.peek(Token![=]) {
+            let eq_token: Token![=] = input.parse()?;
+            if let FnArg::Receiver(_) = &fn_arg {
+                return Err(Error::new(
+                    eq_token.span,
+                    "method receiver cannot have a default value",
+                ));
+            }
+            let expr: Expr = input.parse()?;
+            Some(ArgDefault { eq_token, expr })
+        } else {
+            None
+        };
+
+        Ok(SectionArg { fn_arg, default })
+    }
+}
+
+fn parse_fn_args(input: ParseStream) -> Result<Punctuated<SectionArg, Token![,]>> {
+    let mut args = Punctuated::<SectionArg, Token![,]>::new();
     let mut has_receiver = false;
+    let mut has_default = false;
 
     while !input.is_empty() {
-        let attrs = input.call(Attribute::parse_outer)?;
+        let arg: SectionArg = input.parse()?;
 
-        let arg = parse_fn_arg(input, attrs)?;
+        if !args.is_empty() && has_default != arg.default.is_some() {
+            return Err(Error::new(
+                arg.fn_arg.span(),
+                "either all or none of the arguments of a section must have a default value",
+            ));
+        }
+        has_default = arg.default.is_some();
 
-        match &arg {
+        match &arg.fn_arg {
             FnArg::Receiver(receiver) if has_receiver => {
                 return Err(Error::new(
                     receiver.self_token.span,
@@ -123,6 +286,30 @@ mod tests {
             "find < 'a > (collection : & 'a Collection)"
         );
 
+        let tokens = quote!(at_most(n: usize = 1, strict: bool = n < 4));
+
+        asserts::tokens_are_matching!(
+            Section,
+            tokens,
+            "at_most (n : usize = 1 , strict : bool = n < 4)"
+        );
+
+        let tokens = quote!(from<'a>(user_agent: &'a str)? as RequestFrom);
+
+        asserts::tokens_are_matching!(
+            Section,
+            tokens,
+            "from < 'a > (user_agent : & 'a str) ? as RequestFrom"
+        );
+
+        let tokens = quote!(at_most(n: usize = 1, strict: bool));
+
+        asserts::tokens_are_not_matching!(
+            Section,
+            tokens,
+            "either all or none of the arguments of a section must have a default value"
+        );
+
         let tokens = quote!(f(,));
 
         asserts::tokens_are_not_matching!(
@@ -1,21 +1,27 @@
+use super::config::GenerationConfig;
 use super::trunk::Trunk;
 use proc_macro2::TokenStream;
 use quote::ToTokens;
 use syn::parse::{Parse, ParseStream};
-use syn::Result;
+use syn::{Attribute, Result};
 
 pub struct Tree {
+    pub config: GenerationConfig,
     pub roots: Vec<Trunk>,
 }
 
 impl Parse for Tree {
     fn parse(input: ParseStream) -> Result<Self> {
+        let attrs = input.call(Attribute::parse_inner)?;
+        let config = GenerationConfig::extract_from(attrs)?;
         let mut roots = Vec::new();
         while !input.is_empty() {
-            let trunk: Trunk = input.parse()?;
+            let mut trunk: Trunk = input.parse()?;
+            trunk.split_documentation(&config.doc_separator);
+            trunk.inherit_layout(config.layout);
             roots.push(trunk);
         }
-        Ok(Tree { roots })
+        Ok(Tree { config, roots })
     }
 }
 
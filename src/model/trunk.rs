@@ -1,9 +1,15 @@
-use super::branch::{Branch, DocumentedBranch};
+use super::branch::{Branch, BranchTail, DocumentedBranch};
+use super::options::Layout;
 use super::prelude::{Intro, Prelude};
-use proc_macro2::TokenStream;
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
 use quote::ToTokens;
 use syn::parse::{Parse, ParseStream};
-use syn::{braced, Error, Generics, Result, Token};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{
+    braced, Attribute, Error, Generics, ImplItem, Result, Token, TraitItem, Type, TypeParamBound,
+    Visibility,
+};
 
 pub struct FnTrunk {
     pub prelude: Prelude,
@@ -14,10 +20,19 @@ pub struct FnTrunk {
 pub struct ImplHeader {
     pub impl_token: Token![impl],
     pub generics: Generics,
+    pub trait_: Option<(syn::Path, Token![for])>,
     pub self_ty: syn::Type,
     pub brace_token: syn::token::Brace,
 }
 
+pub struct TraitHeader {
+    pub trait_token: Token![trait],
+    pub ident: syn::Ident,
+    pub generics: Generics,
+    pub supertraits: Option<(Token![:], Punctuated<TypeParamBound, Token![+]>)>,
+    pub brace_token: syn::token::Brace,
+}
+
 pub enum TrunkAlternative {
     Fn {
         fn_token: Token![fn],
@@ -25,6 +40,12 @@ pub enum TrunkAlternative {
     },
     Impl {
         header: ImplHeader,
+        items: Vec<ImplItem>,
+        fn_trunks: Vec<FnTrunk>,
+    },
+    Trait {
+        header: TraitHeader,
+        items: Vec<TraitItem>,
         fn_trunks: Vec<FnTrunk>,
     },
 }
@@ -34,11 +55,50 @@ pub struct Trunk {
     pub alternative: TrunkAlternative,
 }
 
+fn parse_first_branch(input: ParseStream) -> Result<Branch> {
+    let branch: Branch = input.parse()?;
+    let section = &branch.section;
+    if let Some(async_token) = &section.asyncness {
+        return Err(Error::new(
+            async_token.span,
+            "the first section of a method chain cannot be async, use `async fn` to make its last sections async",
+        ));
+    }
+    if let BranchTail::Alternative {
+        body: Some(body), ..
+    } = &branch.tail
+    {
+        return Err(Error::new(
+            body.brace.span.join(),
+            "the first section of a method chain cannot have an intermediate body",
+        ));
+    }
+    if let Some(question_token) = &section.question_token {
+        return Err(Error::new(
+            question_token.span,
+            "the first section of a method chain cannot be optional",
+        ));
+    }
+    if let Some(repetition) = &section.repetition {
+        return Err(Error::new(
+            repetition.star_token.span,
+            "the first section of a method chain cannot be repeated",
+        ));
+    }
+    if section.is_defaulted() {
+        return Err(Error::new(
+            section.paren_token.span.join(),
+            "the first section of a method chain cannot have default values",
+        ));
+    }
+    Ok(branch)
+}
+
 impl Parse for FnTrunk {
     fn parse(input: ParseStream) -> Result<Self> {
         let intro: Intro = input.parse()?;
         let fn_token: Token![fn] = input.parse()?;
-        let branch: Branch = input.parse()?;
+        let branch = parse_first_branch(input)?;
 
         let (prelude, doc_block) = intro.split();
 
@@ -52,6 +112,54 @@ impl Parse for FnTrunk {
     }
 }
 
+// Implementors of a trait would have to provide one flattened method per order of an `any` group.
+fn check_no_any_group(tokens: TokenStream) -> Result<()> {
+    let mut follows_dot = false;
+    for tree in tokens {
+        if let TokenTree::Group(group) = &tree {
+            match group.stream().into_iter().next() {
+                Some(TokenTree::Ident(ident))
+                    if follows_dot && group.delimiter() == Delimiter::Brace && ident == "any" =>
+                {
+                    return Err(Error::new(
+                        ident.span(),
+                        "`any` groups are not supported in trait definitions",
+                    ));
+                }
+                _ => check_no_any_group(group.stream())?,
+            }
+        }
+        follows_dot = matches!(&tree, TokenTree::Punct(punct) if punct.as_char() == '.');
+    }
+    Ok(())
+}
+
+// Method chains can only end without a body inside trait definitions.
+fn check_bodies(branch: &Branch) -> Result<()> {
+    match &branch.tail {
+        BranchTail::Alternative { rest, .. } => {
+            check_bodies(&rest.0.branch)?;
+            for documented in &rest.1 {
+                check_bodies(&documented.branch)?;
+            }
+            Ok(())
+        }
+        BranchTail::Leaf { .. } => Ok(()),
+        BranchTail::Declaration { semi_token, .. } => Err(Error::new(
+            semi_token.span,
+            "only method chains declared in traits can end without a body",
+        )),
+    }
+}
+
+// Associated types and constants are left untouched.
+fn is_associated_item(input: ParseStream) -> Result<bool> {
+    let fork = input.fork();
+    fork.call(Attribute::parse_outer)?;
+    fork.parse::<Visibility>()?;
+    Ok(fork.peek(Token![type]) || (fork.peek(Token![const]) && !fork.peek2(Token![fn])))
+}
+
 impl Parse for Trunk {
     fn parse(input: ParseStream) -> Result<Self> {
         // We let the compiler handle correct use of visibility and asyncness:
@@ -60,7 +168,8 @@ impl Parse for Trunk {
 
         if input.peek(Token![fn]) {
             let fn_token: Token![fn] = input.parse()?;
-            let branch: Branch = input.parse()?;
+            let branch = parse_first_branch(input)?;
+            check_bodies(&branch)?;
 
             let (prelude, doc_block) = intro.split();
 
@@ -93,7 +202,17 @@ impl Parse for Trunk {
                 Generics::default()
             };
 
-            let self_ty: syn::Type = input.parse()?;
+            let ty: Type = input.parse()?;
+            let (trait_, self_ty) = if input.peek(Token![for]) {
+                let for_token: Token![for] = input.parse()?;
+                let path = match ty {
+                    Type::Path(type_path) if type_path.qself.is_none() => type_path.path,
+                    ty => return Err(Error::new(ty.span(), "expected a trait path")),
+                };
+                (Some((path, for_token)), input.parse()?)
+            } else {
+                (None, ty)
+            };
             generics.where_clause = input.parse()?;
 
             let content;
@@ -102,27 +221,128 @@ impl Parse for Trunk {
             let header = ImplHeader {
                 impl_token,
                 generics,
+                trait_,
                 self_ty,
                 brace_token,
             };
 
+            let mut items = Vec::<ImplItem>::new();
+            let mut fn_trunks = Vec::<FnTrunk>::new();
+
+            while !content.is_empty() {
+                if is_associated_item(&content)? {
+                    items.push(content.parse()?);
+                } else {
+                    let mut fn_trunk: FnTrunk = content.parse()?;
+                    check_bodies(&fn_trunk.documented.branch)?;
+                    fn_trunk.prelude.options.inherit_from(&intro.options);
+                    fn_trunks.push(fn_trunk);
+                }
+            }
+
+            let (prelude, _) = intro.split();
+
+            let alternative = TrunkAlternative::Impl {
+                header,
+                items,
+                fn_trunks,
+            };
+
+            Ok(Self {
+                prelude,
+                alternative,
+            })
+        } else if input.peek(Token![trait]) {
+            let trait_token: Token![trait] = input.parse()?;
+            let ident: syn::Ident = input.parse()?;
+            let mut generics: Generics = input.parse()?;
+            let supertraits = if input.peek(Token![:]) {
+                let colon_token: Token![:] = input.parse()?;
+                let mut bounds = Punctuated::<TypeParamBound, Token![+]>::new();
+                while !input.peek(Token![where]) && !input.peek(syn::token::Brace) {
+                    bounds.push_value(input.parse()?);
+                    if !input.peek(Token![+]) {
+                        break;
+                    }
+                    bounds.push_punct(input.parse()?);
+                }
+                Some((colon_token, bounds))
+            } else {
+                None
+            };
+            generics.where_clause = input.parse()?;
+
+            let content;
+            let brace_token = braced!(content in input);
+
+            let header = TraitHeader {
+                trait_token,
+                ident,
+                generics,
+                supertraits,
+                brace_token,
+            };
+
+            check_no_any_group(content.fork().parse()?)?;
+
+            let mut items = Vec::<TraitItem>::new();
             let mut fn_trunks = Vec::<FnTrunk>::new();
 
             while !content.is_empty() {
-                let fn_trunk: FnTrunk = content.parse()?;
-                fn_trunks.push(fn_trunk);
+                if is_associated_item(&content)? {
+                    items.push(content.parse()?);
+                } else {
+                    let mut fn_trunk: FnTrunk = content.parse()?;
+                    fn_trunk.prelude.options.inherit_from(&intro.options);
+                    fn_trunks.push(fn_trunk);
+                }
             }
 
             let (prelude, _) = intro.split();
 
-            let alternative = TrunkAlternative::Impl { header, fn_trunks };
+            let alternative = TrunkAlternative::Trait {
+                header,
+                items,
+                fn_trunks,
+            };
 
             Ok(Self {
                 prelude,
                 alternative,
             })
         } else {
-            Err(Error::new(input.span(), "expected one of: `fn`, `impl`"))
+            Err(Error::new(
+                input.span(),
+                "expected one of: `fn`, `impl`, `trait`",
+            ))
+        }
+    }
+}
+
+impl Trunk {
+    // The layout configured for the whole invocation is only known once it is parsed.
+    pub fn inherit_layout(&mut self, layout: Option<Layout>) {
+        match &mut self.alternative {
+            TrunkAlternative::Fn { .. } => self.prelude.options.inherit_layout(layout),
+            TrunkAlternative::Impl { fn_trunks, .. }
+            | TrunkAlternative::Trait { fn_trunks, .. } => {
+                for fn_trunk in fn_trunks {
+                    fn_trunk.prelude.options.inherit_layout(layout);
+                }
+            }
+        }
+    }
+
+    // Documentation is split once the separator is known, after the whole invocation is parsed.
+    pub fn split_documentation(&mut self, separator: &str) {
+        match &mut self.alternative {
+            TrunkAlternative::Fn { documented, .. } => documented.split_documentation(separator),
+            TrunkAlternative::Impl { fn_trunks, .. }
+            | TrunkAlternative::Trait { fn_trunks, .. } => {
+                for fn_trunk in fn_trunks {
+                    fn_trunk.documented.split_documentation(separator);
+                }
+            }
         }
     }
 }
@@ -148,13 +368,47 @@ impl ToTokens for Trunk {
                 fn_token.to_tokens(tokens);
                 documented.branch.to_tokens(tokens);
             }
-            TrunkAlternative::Impl { header, fn_trunks } => {
+            TrunkAlternative::Impl {
+                header,
+                items,
+                fn_trunks,
+            } => {
                 self.prelude.to_tokens(tokens);
                 header.impl_token.to_tokens(tokens);
                 header.generics.to_tokens(tokens);
+                if let Some((path, for_token)) = &header.trait_ {
+                    path.to_tokens(tokens);
+                    for_token.to_tokens(tokens);
+                }
                 header.self_ty.to_tokens(tokens);
                 header.generics.where_clause.to_tokens(tokens);
                 header.brace_token.surround(tokens, |tokens| {
+                    for item in items {
+                        item.to_tokens(tokens);
+                    }
+                    for fn_trunk in fn_trunks {
+                        fn_trunk.to_tokens(tokens);
+                    }
+                });
+            }
+            TrunkAlternative::Trait {
+                header,
+                items,
+                fn_trunks,
+            } => {
+                self.prelude.to_tokens(tokens);
+                header.trait_token.to_tokens(tokens);
+                header.ident.to_tokens(tokens);
+                header.generics.to_tokens(tokens);
+                if let Some((colon_token, bounds)) = &header.supertraits {
+                    colon_token.to_tokens(tokens);
+                    bounds.to_tokens(tokens);
+                }
+                header.generics.where_clause.to_tokens(tokens);
+                header.brace_token.surround(tokens, |tokens| {
+                    for item in items {
+                        item.to_tokens(tokens);
+                    }
                     for fn_trunk in fn_trunks {
                         fn_trunk.to_tokens(tokens);
                     }
@@ -178,6 +432,14 @@ mod tests {
         let tokens = quote!(fn first.second() {});
 
         asserts::tokens_are_not_matching!(Trunk, tokens, "expected parentheses");
+
+        let tokens = quote!(fn first()?.second() {});
+
+        asserts::tokens_are_not_matching!(
+            Trunk,
+            tokens,
+            "the first section of a method chain cannot be optional"
+        );
     }
 
     #[test]
@@ -214,4 +476,74 @@ mod tests {
             "impl Intro { fn first () . second () { } fn third () . fourth () { } }"
         );
     }
+
+    #[test]
+    fn parse_trunk_with_trait_impl() {
+        let tokens = quote!(
+            impl<T> Describe<T> for Point
+            where T: Debug
+            {
+                type Describer<'a> = describe::Output<'a>;
+                const NAME: &'static str = "point";
+                fn describe(&self).with(prefix: T) -> String { todo!() }
+            }
+        );
+
+        asserts::tokens_are_matching!(
+            Trunk,
+            tokens,
+            "impl < T > Describe < T > for Point where T : Debug {
+                type Describer < 'a > = describe :: Output < 'a > ;
+                const NAME : & 'static str = \"point\" ;
+                fn describe (& self) . with (prefix : T) -> String { todo ! () }
+            }"
+        );
+
+        let tokens = quote!(impl &'static dyn Describe for Point {});
+
+        asserts::tokens_are_not_matching!(Trunk, tokens, "expected a trait path");
+    }
+
+    #[test]
+    fn parse_trunk_with_trait() {
+        let tokens = quote!(
+            pub trait Repository<T>: Clone
+            where T: Debug
+            {
+                type Item;
+                fn find(&self, id: u64).with_deleted(flag: bool) -> T;
+                fn find(&self, id: u64).first() -> T { todo!() }
+            }
+        );
+
+        asserts::tokens_are_matching!(
+            Trunk,
+            tokens,
+            "pub trait Repository < T > : Clone where T : Debug {
+                type Item ;
+                fn find (& self , id : u64) . with_deleted (flag : bool) -> T ;
+                fn find (& self , id : u64) . first () -> T { todo ! () }
+            }"
+        );
+
+        let tokens = quote!(
+            trait Repository {
+                fn find(&self, id: u64).{ any fn with_deleted(flag: bool); fn in_cache(); }.get() -> Item;
+            }
+        );
+
+        asserts::tokens_are_not_matching!(
+            Trunk,
+            tokens,
+            "`any` groups are not supported in trait definitions"
+        );
+
+        let tokens = quote!(fn find(id: u64).with_deleted(flag: bool) -> Item;);
+
+        asserts::tokens_are_not_matching!(
+            Trunk,
+            tokens,
+            "only method chains declared in traits can end without a body"
+        );
+    }
 }
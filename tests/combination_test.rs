@@ -12,6 +12,7 @@ pub struct Date(u32);
 #[derive(PartialEq, Eq)]
 pub struct Nat32(u32);
 
+#[allow(clippy::from_over_into, clippy::needless_lifetimes)]
 impl<'a> Into<Nat32> for &'a Date {
     fn into(self) -> Nat32 {
         Nat32(self.0)
@@ -90,6 +91,38 @@ async fn convert_method_chain_with_async_logic() {
     }
 }
 
+#[tokio::test]
+async fn convert_method_chain_with_async_intermediate_sections() {
+    assemblist! {
+        pub async fn connect_to(host: &'static str)
+            .async authenticated_with(token: u32) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                assert_ne!(0, token, "rejected token");
+            }
+            .{
+                fn fetch(id: u32) -> String {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    format!("{host}/{token}/{id}")
+                }
+                fn ping() -> bool { !host.is_empty() && 0 < token }
+            }
+    };
+
+    let item = connect_to("localhost")
+        .authenticated_with(42)
+        .await
+        .fetch(7)
+        .await;
+    assert_eq!("localhost/42/7", item);
+    assert!(
+        connect_to("localhost")
+            .authenticated_with(1)
+            .await
+            .ping()
+            .await
+    );
+}
+
 #[test]
 fn convert_method_chain_with_two_references() {
     assemblist! {
@@ -115,6 +148,7 @@ fn convert_method_chain_with_two_references() {
 }
 
 #[test]
+#[allow(clippy::manual_swap)]
 fn convert_method_chain_with_two_double_references() {
     assemblist! {
         fn swap<'a, 'x, T>(item1: &'a mut &'x T).with<'b>(item2: &'b mut &'x T) {
@@ -135,3 +169,39 @@ fn convert_method_chain_with_two_double_references() {
     assert_eq!("Hola", r1.as_str());
     assert_eq!("Bonjour", r2.as_str());
 }
+
+pub struct Greeting;
+
+assemblist! {
+    /// Greets someone.
+    /// ---
+    /// Politely.
+    pub fn greet(name: &str).politely() -> String { format!("Good morning, {name}.") }
+
+    /// Greets someone.
+    /// ---
+    /// Casually.
+    pub fn greet(name: &str).casually() -> String { format!("Hi {name}!") }
+
+    pub fn greet(name: &str).with(word: &str).loudly() -> String {
+        format!("{}, {}!", word.to_uppercase(), name.to_uppercase())
+    }
+    pub fn greet(name: &str).with(word: &str).quietly() -> String {
+        format!("{word}, {name}...")
+    }
+
+    impl Greeting {
+        pub fn welcome(name: &str).formally() -> String { format!("Dear {name},") }
+        pub fn welcome(name: &str).casually() -> String { format!("Hi {name}!") }
+    }
+}
+
+#[test]
+fn merge_method_chains_sharing_prefixes() {
+    assert_eq!("Good morning, Ada.", greet("Ada").politely());
+    assert_eq!("Hi Ada!", greet("Ada").casually());
+    assert_eq!("HELLO, ADA!", greet("Ada").with("Hello").loudly());
+    assert_eq!("Hello, Ada...", greet("Ada").with("Hello").quietly());
+    assert_eq!("Dear Ada,", Greeting::welcome("Ada").formally());
+    assert_eq!("Hi Ada!", Greeting::welcome("Ada").casually());
+}
@@ -45,6 +45,377 @@ fn convert_method_chain_for_movies() {
     assert_eq!(f(3), 8);
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub enum MovieError {
+    TooEarly(usize),
+    Anonymous,
+}
+
+fn define_checked_movie(
+    name: &str,
+    release_year: usize,
+    director_name: &str,
+) -> Result<Movie, MovieError> {
+    assemblist! {
+        fn define_movie<'a>(name: &'a str)
+            .released_in(release_year: usize) -> Result<_, MovieError> {
+                if release_year < 1888 {
+                    Err(MovieError::TooEarly(release_year))?
+                }
+            }
+            .directed_by(director_name: &'a str) -> Option<_> {
+                if director_name.is_empty() {
+                    None?
+                }
+            }
+            .build() -> Movie
+        {
+            Movie {
+                name: name.to_string(),
+                release_year,
+                director_name: director_name.to_string(),
+            }
+        }
+    };
+
+    let movie = define_movie(name)
+        .released_in(release_year)?
+        .directed_by(director_name)
+        .ok_or(MovieError::Anonymous)?
+        .build();
+    Ok(movie)
+}
+
+#[test]
+fn convert_method_chain_with_fallible_sections() {
+    assert_eq!(
+        Ok(Movie {
+            name: "The Lobster".to_string(),
+            release_year: 2015,
+            director_name: "Yorgos Lanthimos".to_string()
+        }),
+        define_checked_movie("The Lobster", 2015, "Yorgos Lanthimos")
+    );
+    assert_eq!(
+        Err(MovieError::TooEarly(1700)),
+        define_checked_movie("The Lobster", 1700, "Yorgos Lanthimos")
+    );
+    assert_eq!(
+        Err(MovieError::Anonymous),
+        define_checked_movie("The Lobster", 2015, "")
+    );
+}
+
+#[test]
+fn convert_method_chain_with_derived_values() {
+    assemblist! {
+        fn request_to(host: &str)
+            .at(path: &str) -> Option<_> {
+                let url: String = format!("https://{}/{}", host.trim_end_matches('/'), path.trim_start_matches('/'));
+                let mut depth: usize = 0;
+                for segment in path.split('/') {
+                    if !segment.is_empty() {
+                        depth += 1;
+                    }
+                }
+                if depth == 0 {
+                    None?
+                }
+            }
+            .{
+                fn get() -> String { format!("GET {url} ({depth}) [{host}|{path}]") }
+                fn post_to_parent(body: &str) -> String {
+                    let depth = depth - 1;
+                    format!("POST {url}/.. ({depth}) [{host}|{path}] {body}")
+                }
+            }
+    }
+
+    assert_eq!(
+        "GET https://example.com/a/b (2) [example.com/|/a/b]",
+        request_to("example.com/").at("/a/b").unwrap().get()
+    );
+    assert_eq!(
+        "POST https://example.com/a/b/.. (1) [example.com|a/b] {}",
+        request_to("example.com")
+            .at("a/b")
+            .unwrap()
+            .post_to_parent("{}")
+    );
+    assert!(request_to("example.com").at("/").is_none());
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct PartialMovie {
+    name: String,
+    release_year: Option<usize>,
+    director_name: String,
+}
+
+#[test]
+fn convert_method_chain_with_derived_traits() {
+    assemblist! {
+        #[assemblist(derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord))]
+        fn define_movie<'a>(name: &'a str)
+            .released_in(release_year: usize)
+            .directed_by(director_name: &'a str) -> Movie
+        {
+            Movie {
+                name: name.to_string(),
+                release_year,
+                director_name: director_name.to_string(),
+            }
+        }
+    }
+
+    let prefix = define_movie("The Lobster").released_in(2015);
+    let copy = prefix.clone();
+    assert_eq!(prefix, copy);
+    assert!(define_movie("Dogtooth").released_in(2009) < copy);
+    assert_eq!(
+        "Output { release_year: 2015, name: \"The Lobster\" }",
+        format!("{copy:?}")
+    );
+
+    let first = prefix.directed_by("Yorgos Lanthimos");
+    let second = copy.directed_by("Someone Else");
+    assert_eq!(first.name, second.name);
+    assert_ne!(first.director_name, second.director_name);
+}
+
+pub struct Ticket(usize);
+
+#[test]
+fn convert_method_chain_with_derived_traits_on_unsupported_fields() {
+    assemblist! {
+        #[assemblist(derive(Clone, Debug))]
+        fn book(ticket: Ticket).for_seat(seat: usize) -> (usize, usize) {
+            (ticket.0, seat)
+        }
+    }
+
+    assert_eq!((3, 12), book(Ticket(3)).for_seat(12));
+}
+
+#[test]
+fn convert_method_chain_with_accessors() {
+    assemblist! {
+        #[assemblist(accessors)]
+        fn define_movie<'a>(name: &'a str)
+            .released_in(release_year: usize)
+            .directed_by(director_name: &'a str) -> Movie
+        {
+            Movie {
+                name: name.to_string(),
+                release_year,
+                director_name: director_name.to_string(),
+            }
+        }
+    }
+
+    let partial = define_movie("The Lobster").released_in(2015);
+    assert_eq!(&"The Lobster", partial.name());
+    assert_eq!(&2015, partial.release_year());
+
+    let parts = partial.into_parts();
+    assert_eq!(("The Lobster", 2015), parts);
+
+    let resumed = define_movie::released_in::Output::from_parts(parts);
+    let movie = resumed.directed_by("Yorgos Lanthimos");
+    assert_eq!("The Lobster", movie.name);
+    assert_eq!(2015, movie.release_year);
+}
+
+#[test]
+fn convert_method_chain_with_accessors_on_repeated_sections_and_patterns() {
+    assemblist! {
+        #[assemblist(accessors)]
+        fn request_to<'a>(url: &'a str)
+            .authenticated_as((user, password): (&'a str, &'a str))
+            .header(name: &'a str, value: &'a str)*
+            .send() -> String
+        {
+            format!("{user}:{password}@{url} {header:?}")
+        }
+    }
+
+    let partial = request_to("/home")
+        .authenticated_as(("admin", "1234"))
+        .header("Accept", "text/html");
+    assert_eq!(&"/home", partial.url());
+    assert_eq!(&vec![("Accept", "text/html")], partial.header_list());
+
+    let (url, credentials, header) = partial.into_parts();
+    assert_eq!(("/home", ("admin", "1234")), (url, credentials));
+    assert_eq!(vec![("Accept", "text/html")], header);
+}
+
+#[test]
+fn convert_method_chain_with_optional_section() {
+    assemblist! {
+        fn define_movie<'a>(name: &'a str)
+            .released_in(release_year: usize)?
+            .directed_by(director_name: &'a str) -> PartialMovie
+        {
+            PartialMovie {
+                name: name.to_string(),
+                release_year,
+                director_name: director_name.to_string(),
+            }
+        }
+    };
+
+    let movie = define_movie("The Lobster").directed_by("Yorgos Lanthimos");
+
+    assert_eq!(
+        movie,
+        PartialMovie {
+            name: "The Lobster".to_string(),
+            release_year: None,
+            director_name: "Yorgos Lanthimos".to_string()
+        }
+    );
+
+    let movie = define_movie("The Lobster")
+        .released_in(2015)
+        .directed_by("Yorgos Lanthimos");
+
+    assert_eq!(movie.release_year, Some(2015));
+}
+
+#[test]
+fn convert_method_chain_with_consecutive_optional_sections() {
+    assemblist! {
+        fn replace_in<'a>(text: &'a str)
+            .occurrences_of(pattern: &'a str)
+            .with(replacement: &'a str)
+            .at_most(n: usize)?
+            .ignoring_case(ignore_case: bool)?
+            .{
+                fn times() -> String {
+                    let text = if ignore_case.unwrap_or(false) { text.to_lowercase() } else { text.to_string() };
+                    match n {
+                        Some(n) => text.replacen(pattern, replacement, n),
+                        None => text.replace(pattern, replacement),
+                    }
+                }
+                fn described() -> String {
+                    format!("{text}: {pattern} -> {replacement} ({n:?}, {ignore_case:?})")
+                }
+            }
+    };
+
+    assert_eq!(
+        "xbxbab",
+        replace_in("ababab")
+            .occurrences_of("a")
+            .with("x")
+            .at_most(2)
+            .times()
+    );
+    assert_eq!(
+        "xbxbxb",
+        replace_in("ababab").occurrences_of("a").with("x").times()
+    );
+    assert_eq!(
+        "xbxbxb",
+        replace_in("AbAbAb")
+            .occurrences_of("a")
+            .with("x")
+            .ignoring_case(true)
+            .times()
+    );
+    assert_eq!(
+        "ab: a -> x (Some(1), Some(false))",
+        replace_in("ab")
+            .occurrences_of("a")
+            .with("x")
+            .at_most(1)
+            .ignoring_case(false)
+            .described()
+    );
+    assert_eq!(
+        "ab: a -> x (None, Some(true))",
+        replace_in("ab")
+            .occurrences_of("a")
+            .with("x")
+            .ignoring_case(true)
+            .described()
+    );
+}
+
+#[test]
+fn convert_method_chain_with_repeated_sections() {
+    assemblist! {
+        fn request_to<'a>(url: &'a str)
+            .header(name: &'a str, value: &'a str)*
+            .tag(tag: &'a str)*[..=2]
+            .send() -> String
+        {
+            let headers = header
+                .iter()
+                .map(|(name, value)| format!("{name}={value}"))
+                .collect::<Vec<_>>();
+            format!("{url} [{}] {tag:?}", headers.join(", "))
+        }
+    };
+
+    assert_eq!("/home [] []", request_to("/home").send());
+    assert_eq!(
+        "/home [Accept=text/html] []",
+        request_to("/home").header("Accept", "text/html").send()
+    );
+    assert_eq!(
+        "/home [Accept=text/html, Host=localhost] [\"urgent\", \"retry\"]",
+        request_to("/home")
+            .header("Accept", "text/html")
+            .header("Host", "localhost")
+            .tag("urgent")
+            .tag("retry")
+            .send()
+    );
+    assert_eq!(
+        "/home [] [\"urgent\"]",
+        request_to("/home").tag("urgent").send()
+    );
+}
+
+#[test]
+fn convert_method_chain_with_sections_in_any_order() {
+    assemblist! {
+        fn define_movie<'a>(name: &'a str).{
+            any
+            fn released_in(release_year: usize);
+            fn directed_by(director_name: &'a str);
+        }.build() -> PartialMovie
+        {
+            PartialMovie {
+                name: name.to_string(),
+                release_year: Some(release_year),
+                director_name: director_name.to_string(),
+            }
+        }
+    };
+
+    let expected = PartialMovie {
+        name: "The Lobster".to_string(),
+        release_year: Some(2015),
+        director_name: "Yorgos Lanthimos".to_string(),
+    };
+
+    let movie = define_movie("The Lobster")
+        .released_in(2015)
+        .directed_by("Yorgos Lanthimos")
+        .build();
+    assert_eq!(movie, expected);
+
+    let movie = define_movie("The Lobster")
+        .directed_by("Yorgos Lanthimos")
+        .released_in(2015)
+        .build();
+    assert_eq!(movie, expected);
+}
+
 pub struct MultiPtr<'a, 'b, T, U> {
     ptr1: &'a T,
     ptr2: &'b U,
@@ -179,6 +550,238 @@ fn convert_method_chain_for_http_requests() {
     assert_eq!(post_request.body, HttpBody::Text("Hello world".to_string()));
 }
 
+#[test]
+fn convert_method_chain_with_reusable_prefix() {
+    assemblist! {
+        #[assemblist(by_ref)]
+        fn new_http_request_to(url: Uri)
+            .from<'a>(user_agent: &'a str).{
+
+            fn as_get(authorization: HttpAuthorization) -> GetHttpRequest {
+                GetHttpRequest {
+                    url,
+                    user_agent: user_agent.to_string(),
+                    authorization,
+                }
+            }
+
+            fn as_post(authorization: HttpAuthorization, body: String) -> PostHttpRequest {
+                PostHttpRequest {
+                    url,
+                    user_agent: user_agent.to_string(),
+                    authorization,
+                    body: HttpBody::Text(body),
+                }
+            }
+        }
+    };
+
+    let template =
+        new_http_request_to(Uri::from_static("http://www.croco-paradise.tv")).from("FireFox");
+
+    let tokens = ["AEKZEFOEZ", "ZEFOEZAEK"];
+    for token in tokens {
+        let request = template.as_get(HttpAuthorization::Bearer(token.to_string()));
+        assert_eq!(request.url.to_string(), "http://www.croco-paradise.tv/");
+        assert_eq!(request.user_agent, "FireFox");
+        assert_eq!(
+            request.authorization,
+            HttpAuthorization::Bearer(token.to_string())
+        );
+    }
+
+    let request = template.as_post(HttpAuthorization::None, "Hello world".to_string());
+    assert_eq!(request.body, HttpBody::Text("Hello world".to_string()));
+}
+
+#[test]
+fn convert_method_chain_with_flat_layout() {
+    assemblist! {
+        #[assemblist(naming = "{}Builder")]
+        fn new_http_request_to(url: Uri)
+            .from<'a>(user_agent: &'a str) as RequestDraft
+            .with_authorization(authorization: HttpAuthorization).{
+
+            fn as_get() -> GetHttpRequest {
+                GetHttpRequest {
+                    url,
+                    user_agent: user_agent.to_string(),
+                    authorization,
+                }
+            }
+
+            fn as_post(body: String) -> PostHttpRequest {
+                PostHttpRequest {
+                    url,
+                    user_agent: user_agent.to_string(),
+                    authorization,
+                    body: HttpBody::Text(body),
+                }
+            }
+        }
+    };
+
+    fn authorize<'a>(
+        draft: RequestDraft<'a>,
+        token: &str,
+    ) -> NewHttpRequestToFromWithAuthorizationBuilder<'a> {
+        draft.with_authorization(HttpAuthorization::Bearer(token.to_string()))
+    }
+
+    let partial: NewHttpRequestToBuilder =
+        new_http_request_to(Uri::from_static("http://www.croco-paradise.tv"));
+    let draft = partial.from("FireFox");
+    let request = authorize(draft, "ZEFOEZAEK").as_get();
+
+    assert_eq!(request.url.to_string(), "http://www.croco-paradise.tv/");
+    assert_eq!(request.user_agent, "FireFox");
+    assert_eq!(
+        request.authorization,
+        HttpAuthorization::Bearer("ZEFOEZAEK".to_string())
+    );
+}
+
+#[test]
+fn convert_method_chain_with_qualified_alternatives() {
+    assemblist! {
+        fn new_http_request_to(url: Uri)
+            .from<'a>(user_agent: &'a str).{
+
+            #[must_use]
+            #[track_caller]
+            pub(crate) fn get() -> GetHttpRequest {
+                GetHttpRequest {
+                    url,
+                    user_agent: user_agent.to_string(),
+                    authorization: HttpAuthorization::None,
+                }
+            }
+
+            #[cfg(any())]
+            fn through(proxy: UnknownProxy).get() -> GetHttpRequest {
+                unknown_function(proxy)
+            }
+
+            #[deprecated = "use `get` instead"]
+            fn legacy_get() -> GetHttpRequest {
+                GetHttpRequest {
+                    url,
+                    user_agent: user_agent.to_string(),
+                    authorization: HttpAuthorization::None,
+                }
+            }
+
+            /// # Safety
+            /// `body` must point to `len` bytes of valid UTF-8.
+            unsafe fn post(body: *const u8, len: usize) -> PostHttpRequest {
+                let bytes = std::slice::from_raw_parts(body, len);
+                PostHttpRequest {
+                    url,
+                    user_agent: user_agent.to_string(),
+                    authorization: HttpAuthorization::None,
+                    body: HttpBody::Text(std::str::from_utf8_unchecked(bytes).to_string()),
+                }
+            }
+        }
+    };
+
+    let request = new_http_request_to(Uri::from_static("http://www.croco-paradise.tv"))
+        .from("FireFox")
+        .get();
+    assert_eq!(request.user_agent, "FireFox");
+
+    let body = "Hello world";
+    let request =
+        new_http_request_to(Uri::from_static("http://www.croco-paradise.tv")).from("FireFox");
+    let request = unsafe { request.post(body.as_ptr(), body.len()) };
+    assert_eq!(request.body, HttpBody::Text("Hello world".to_string()));
+}
+
+#[test]
+fn convert_method_chain_with_type_only_sections() {
+    assemblist! {
+        fn parse().as_type<T: std::str::FromStr>().from(text: &str) -> Option<T> {
+            text.trim().parse().ok()
+        }
+
+        fn split<'a>(separator: char).{
+            fn first(text: &'a str) -> &'a str {
+                text.split(separator).next().unwrap_or(text)
+            }
+            fn name() -> Option<&'a str> {
+                (separator == '=').then_some("equals")
+            }
+        }
+    }
+
+    assert_eq!(Some(42u8), parse().as_type::<u8>().from(" 42 "));
+    assert_eq!(None, parse().as_type::<u8>().from("512"));
+    assert_eq!(Some(1.5), parse().as_type::<f64>().from("1.5"));
+
+    assert_eq!("key", split('=').first("key=value"));
+    assert_eq!(Some("equals"), split('=').name());
+    assert_eq!(None, split(',').name());
+}
+
+#[test]
+fn convert_method_chain_in_block_scope() {
+    #[derive(Debug, PartialEq)]
+    struct Ticket {
+        seat: Seat,
+        price: u32,
+        note: Option<String>,
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Seat {
+        Numbered(u16),
+        Standing,
+    }
+
+    struct Venue {
+        base_price: u32,
+    }
+
+    assemblist! {
+        #![assemblist(layout = "flat")]
+
+        fn book(price: u32).note(note: String)?.{
+            fn seat(number: u16) -> Ticket {
+                Ticket { seat: Seat::Numbered(number), price, note }
+            }
+            fn standing() -> Ticket {
+                Ticket { seat: Seat::Standing, price, note }
+            }
+        }
+
+        impl Venue {
+            fn sell(&self, discount: u32).seat(number: u16) -> Ticket {
+                book(self.base_price - discount).seat(number)
+            }
+        }
+    }
+
+    assert_eq!(
+        Ticket {
+            seat: Seat::Numbered(12),
+            price: 30,
+            note: None
+        },
+        book(30).seat(12)
+    );
+    assert_eq!(
+        Ticket {
+            seat: Seat::Standing,
+            price: 10,
+            note: Some("late".to_string())
+        },
+        book(10).note("late".to_string()).standing()
+    );
+
+    let venue = Venue { base_price: 50 };
+    assert_eq!(35, venue.sell(15).seat(3).price);
+}
+
 #[test]
 pub fn document_implementations() {
     let text = assemblist_text! {
@@ -355,3 +958,56 @@ pub fn document_implementations() {
         }"
     );
 }
+
+#[test]
+#[deny(unused_variables)]
+fn convert_method_chain_with_values_only_used_by_intermediate_bodies() {
+    assemblist! {
+        fn request_to(host: &str)
+            .at(path: &str) {
+                let url: String = format!("https://{host}/{path}");
+            }
+            .{
+                fn get() -> String { format!("GET {url}") }
+                fn delete() -> String { format!("DELETE {url}") }
+            }
+
+        fn connect_to(host: &str)
+            .authenticated_with(token: u32) {
+                assert_ne!(0, token, "rejected token on {host}");
+            }
+            .fetch(id: u32) -> u32 { id }
+    }
+
+    assert_eq!(
+        "GET https://example.com/a",
+        request_to("example.com").at("a").get()
+    );
+    assert_eq!(
+        "DELETE https://example.com/b",
+        request_to("example.com").at("b").delete()
+    );
+    assert_eq!(7, connect_to("localhost").authenticated_with(42).fetch(7));
+}
+
+mod catalog {
+    use assemblist::assemblist;
+
+    assemblist! {
+        #![assemblist(module_visibility = "pub(crate)")]
+
+        fn find(name: &'static str).on_shelf(shelf: usize) -> String {
+            format!("{name} on shelf {shelf}")
+        }
+    }
+
+    pub(crate) fn find_dictionary() -> find::Output {
+        find("dictionary")
+    }
+}
+
+#[test]
+fn convert_method_chain_with_configured_module_visibility() {
+    let partial: catalog::find::Output = catalog::find_dictionary();
+    assert_eq!("dictionary on shelf 3", partial.on_shelf(3));
+}
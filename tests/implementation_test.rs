@@ -289,13 +289,19 @@ pub fn decompose_generic_implementations_with_self_receiver() {
     let mut x = MyVec::new(vec!["Blue", "Orange", "Red", "Lavender"]);
     let y = MyVec::new(vec![4, 6, 3, 8, 2002]);
 
-    assert!(x.take_at_most(8).comparing_to(&y).applying(|t, n| t.len() == *n));
+    assert!(x
+        .take_at_most(8)
+        .comparing_to(&y)
+        .applying(|t, n| t.len() == *n));
 
     x.at(1).insert("Yellow");
     assert_eq!(5, x._inner.len());
     x.at(2).remove();
 
-    assert!(x.take_at_most(8).comparing_to(&y).applying(|t, n| t.len() == *n));
+    assert!(x
+        .take_at_most(8)
+        .comparing_to(&y)
+        .applying(|t, n| t.len() == *n));
 }
 
 #[test]
@@ -351,7 +357,7 @@ pub fn verify_generic_implementations_with_self_receiver() {
                     pub(super) n : usize,
                 }
                 impl < 'a, 'b, T, U > Output < 'a, 'b, T, U > where T : Debug {
-                    pub fn applying(self, f : impl Fn(& T, & U) -> bool) -> bool where T : Debug {
+                    pub fn applying(self, f : impl Fn(& T, & U) -> bool) -> bool {
                         let other = self.other;
                         let self_ = self.self_;
                         let n = self.n;
@@ -372,3 +378,475 @@ pub fn verify_generic_implementations_with_self_receiver() {
         }"
     );
 }
+
+struct Sentence(String);
+
+assemblist! {
+    impl Sentence {
+        fn words(&self).longer_than(n: usize) -> Vec<&str> {
+            self_.0.split(' ').filter(|word| n < word.len()).collect()
+        }
+    }
+}
+
+#[test]
+pub fn decompose_implementations_with_elided_lifetimes() {
+    let sentence = Sentence("a sentence with several words".to_string());
+    assert_eq!(vec!["sentence", "several"], sentence.words().longer_than(5));
+}
+
+struct Counter(usize);
+
+assemblist! {
+    impl Counter {
+        fn is_boxed_between(self: Box<Self>, a: usize).and(b: usize) -> bool {
+            a <= self_.0 && self_.0 <= b
+        }
+        fn is_shared_between(self: std::rc::Rc<Self>, a: usize).and(b: usize) -> bool {
+            a <= self_.0 && self_.0 <= b
+        }
+        fn increment(self: std::pin::Pin<&mut Self>).by(n: usize) {
+            self_.get_mut().0 += n;
+        }
+    }
+}
+
+#[test]
+pub fn decompose_implementations_with_complex_receivers() {
+    assert!(Box::new(Counter(5)).is_boxed_between(2).and(8));
+    assert!(!std::rc::Rc::new(Counter(5)).is_shared_between(6).and(8));
+
+    let mut counter = Counter(5);
+    std::pin::Pin::new(&mut counter).increment().by(3);
+    assert_eq!(8, counter.0);
+}
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl std::fmt::Debug for Point {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+trait Describe {
+    type Describer<'a>
+    where
+        Self: 'a;
+
+    fn describe(&self) -> Self::Describer<'_>;
+}
+
+assemblist! {
+    impl std::fmt::Display for Point {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "x = {}, y = {}", self.x, self.y)
+        }
+    }
+
+    impl Describe for Point {
+        type Describer<'a> = point_describe::Output<'a>;
+
+        fn describe(&self).with(prefix: &str) -> String {
+            format!("{prefix}{self:?}")
+        }
+    }
+}
+
+#[test]
+pub fn decompose_trait_implementations() {
+    let point = Point { x: 3, y: 4 };
+    assert_eq!("x = 3, y = 4", point.to_string());
+    assert_eq!("point (3, 4)", point.describe().with("point "));
+}
+
+struct LineSegment(Point, Point);
+
+trait Summarize {
+    type Summarizer;
+
+    fn summarize(self) -> Self::Summarizer;
+}
+
+assemblist! {
+    impl Describe for LineSegment {
+        type Describer<'a> = line_segment_describe::Output<'a>;
+
+        fn describe(&self).with(prefix: &str) -> String {
+            format!("{prefix}{:?} → {:?}", self.0, self.1)
+        }
+    }
+
+    #[assemblist(layout = "flat")]
+    impl Summarize for Point {
+        type Summarizer = PointSummarize;
+
+        fn summarize(self).in_words() -> String {
+            format!("a point at {}, {}", self.x, self.y)
+        }
+    }
+
+    #[assemblist(layout = "flat")]
+    impl Summarize for LineSegment {
+        type Summarizer = LineSegmentSummarize;
+
+        fn summarize(self).in_words() -> String {
+            format!("a segment of length {}", (self.1.x - self.0.x).abs() + (self.1.y - self.0.y).abs())
+        }
+    }
+}
+
+#[test]
+pub fn decompose_trait_implementations_for_several_types() {
+    let point = Point { x: 3, y: 4 };
+    let segment = LineSegment(Point { x: 0, y: 0 }, Point { x: 1, y: 2 });
+    assert_eq!("point (3, 4)", point.describe().with("point "));
+    assert_eq!(
+        "segment (0, 0) → (1, 2)",
+        segment.describe().with("segment ")
+    );
+    assert_eq!("a point at 3, 4", point.summarize().in_words());
+    assert_eq!("a segment of length 3", segment.summarize().in_words());
+}
+
+struct Odometer {
+    start: usize,
+}
+
+assemblist! {
+    #![assemblist(inline = "always", receiver = "this", doc_separator = "===")]
+
+    impl Odometer {
+        /// Count from the start of the odometer.
+        ///===
+        /// Provide a step.
+        ///===
+        /// Provide a number of steps.
+        fn count(&self).by(step: usize).times(n: usize) -> usize {
+            this.start + step * n
+        }
+    }
+}
+
+#[test]
+pub fn decompose_implementations_with_configured_generation() {
+    let odometer = Odometer { start: 2 };
+    assert_eq!(17, odometer.count().by(3).times(5));
+
+    let text = assemblist_text! {
+        #![assemblist(inline = "never", doc_separator = "===")]
+
+        /// Start.
+        ///===
+        /// End.
+        fn start(a: u8).end() -> u8 { a }
+    };
+
+    asserts::equivalent!(
+        text,
+        "#[doc = \" Start.\"]
+        #[inline(never)]
+        fn start(a : u8) -> start :: Output { start :: Output { a, } }
+        #[doc = \"Intermediary module for partial method chain [`start`](fn@start)`(…).…`\"]
+        #[doc = \"\"]
+        #[doc = \"Following method chains are supported:\"]
+        #[doc = \"- [`start`](fn@start)`(…).`[`end`](method@start::Output::end)`(…)`\"]
+        mod start
+        {
+            # ! [allow(unused_imports)] use super :: * ;
+            #[doc = \"Intermediary type returned by partial method chain [`start`](fn@super::start)`(…).…`\"]
+            pub struct Output { pub(super) a : u8, }
+            impl Output
+            {
+                #[doc = \" End.\"]
+                pub fn end(self,) -> u8 { let a = self.a; a }
+            }
+        }"
+    );
+}
+
+struct Journal {
+    lines: Vec<String>,
+}
+
+assemblist! {
+    impl Journal {
+        pub fn new().with_capacity(n: usize) -> Self {
+            Self { lines: Vec::with_capacity(n) }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Buffer<T> {
+    items: Vec<T>,
+    label: &'static str,
+}
+
+trait Labelled {
+    type Label;
+}
+
+impl<T> Labelled for Buffer<T> {
+    type Label = &'static str;
+}
+
+impl<T> Buffer<T> {
+    fn empty() -> Self {
+        Self {
+            items: Vec::new(),
+            label: "buffer",
+        }
+    }
+}
+
+assemblist! {
+    impl<T: Clone> Buffer<T> {
+        pub fn allocate().with_capacity(n: usize) -> Self {
+            Self { items: Vec::with_capacity(n), label: "buffer" }
+        }
+
+        pub fn filled(value: T).times(n: usize).labelled(label: <Self as Labelled>::Label) -> Self {
+            let mut buffer = Self::empty();
+            buffer.items.resize(n, value);
+            buffer.label = label;
+            buffer
+        }
+
+        pub fn merge(self).with(other: Self) -> Self {
+            let mut items = self_.items;
+            items.extend(other.items);
+            Self { items, ..self_ }
+        }
+    }
+}
+
+#[test]
+pub fn rewrite_self_in_implementations() {
+    let journal = Journal::new().with_capacity(8);
+    assert!(journal.lines.is_empty() && journal.lines.capacity() >= 8);
+
+    let buffer = Buffer::<char>::allocate().with_capacity(4);
+    assert!(buffer.items.is_empty() && buffer.items.capacity() >= 4);
+
+    let filled = Buffer::filled('a').times(2).labelled("letters");
+    assert_eq!(vec!['a', 'a'], filled.items);
+    assert_eq!("letters", filled.label);
+
+    let merged = filled
+        .merge()
+        .with(Buffer::filled('b').times(1).labelled("other"));
+    assert_eq!(
+        Buffer {
+            items: vec!['a', 'a', 'b'],
+            label: "letters"
+        },
+        merged
+    );
+}
+
+struct Shelf {
+    books: Vec<&'static str>,
+}
+
+assemblist! {
+    impl Shelf {
+        pub fn books(&self).{
+            fn longer_than(n: usize) -> Vec<&'static str> {
+                self.books.iter().copied().filter(|book| n < book.len()).collect()
+            }
+            fn describe() -> String {
+                let count = self.books.len();
+                format!("{count} books, {{self}}: {}", self_.books.join(", "))
+            }
+        }
+
+        pub fn take(self).first(n: usize) -> Self {
+            let books = self.books.into_iter().take(n).collect();
+            Self { books }
+        }
+    }
+}
+
+#[test]
+pub fn refer_to_receiver_with_self() {
+    let shelf = Shelf {
+        books: vec!["Dune", "Hyperion", "Solaris"],
+    };
+    assert_eq!(vec!["Hyperion", "Solaris"], shelf.books().longer_than(4));
+    assert_eq!(
+        "3 books, {self}: Dune, Hyperion, Solaris",
+        shelf.books().describe()
+    );
+    assert_eq!(vec!["Dune"], shelf.take().first(1).books);
+}
+
+struct Pair<T> {
+    left: T,
+}
+
+assemblist! {
+    impl<T> Pair<T> where T: Clone {
+        pub fn zip<U>(&self, right: U) where U: Clone
+            .labelled<L>(label: L) where L: Into<String>
+            .into_tuple() -> (T, Vec<U>, String)
+        {
+            (self.left.clone(), vec![right.clone(), right], label.into())
+        }
+    }
+}
+
+#[test]
+pub fn accumulate_where_clauses() {
+    let pair = Pair { left: 'x' };
+    assert_eq!(
+        ('x', vec![1, 1], "ones".to_string()),
+        pair.zip(1).labelled("ones").into_tuple()
+    );
+
+    let text = assemblist_text! {
+        fn first<T>(a: T) where T: Copy .second<U>(b: U) where U: Default .third() -> (T, U) { (a, b) }
+    };
+
+    asserts::equivalent!(
+        text,
+        "#[inline]
+        fn first < T > (a : T) -> first :: Output :: < T > where T : Copy { first :: Output :: < T > { a, } }
+        #[doc = \"Intermediary module for partial method chain [`first`](fn@first)`(…).…`\"]
+        #[doc = \"\"]
+        #[doc = \"Following method chains are supported:\"]
+        #[doc = \"- [`first`](fn@first)`(…).`[`second`](method@first::Output::second)`(…).`[`third`](method@first::second::Output::third)`(…)`\"]
+        mod first
+        {
+            # ! [allow(unused_imports)] use super :: * ;
+            #[doc = \"Intermediary type returned by partial method chain [`first`](fn@super::first)`(…).…`\"]
+            pub struct Output < T > where T : Copy { pub(super) a : T, }
+            impl < T > Output < T > where T : Copy
+            {
+                #[inline]
+                pub fn second < U > (self, b : U) -> second :: Output :: < T, U > where U : Default
+                { let a = self.a; second :: Output :: < T, U > { b, a, } }
+            }
+            #[doc = \"Intermediary module for partial method chain [`first`](fn@super::first)`(…).`[`second`](method@Output::second)`(…).…`\"]
+            #[doc = \"\"]
+            #[doc = \"Following method chains are supported:\"]
+            #[doc = \"- [`first`](fn@super::first)`(…).`[`second`](method@Output::second)`(…).`[`third`](method@second::Output::third)`(…)`\"]
+            pub mod second
+            {
+                # ! [allow(unused_imports)] use super :: * ;
+                #[doc = \"Intermediary type returned by partial method chain [`first`](fn@super::super::first)`(…).`[`second`](method@super::Output::second)`(…).…`\"]
+                pub struct Output < T, U > where T : Copy, U : Default { pub(super) b : U, pub(super) a : T, }
+                impl < T, U > Output < T, U > where T : Copy, U : Default
+                {
+                    pub fn third(self,) -> (T, U) { let b = self.b; let a = self.a; (a, b) }
+                }
+            }
+        }"
+    );
+}
+
+struct Tags {
+    a: std::collections::HashSet<&'static str>,
+    b: std::collections::HashSet<&'static str>,
+}
+
+assemblist! {
+    impl Tags {
+        pub fn both(&self).count() -> usize {
+            self.a.union(&self.b).count()
+        }
+    }
+}
+
+#[test]
+pub fn refer_to_receiver_in_method_calls_named_after_items() {
+    let tags = Tags {
+        a: ["red", "blue"].into(),
+        b: ["blue", "green"].into(),
+    };
+    assert_eq!(3, tags.both().count());
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Jar(u8);
+
+impl From<u8> for Jar {
+    fn from(value: u8) -> Self {
+        Jar(value)
+    }
+}
+
+assemblist! {
+    impl Jar {
+        pub fn dup(&self).times(n: usize) -> Vec<Self> where Self: Clone {
+            vec![self.clone(); n]
+        }
+
+        pub fn pair<T>(&self, other: T) where T: Into<Self> .collect() -> (Self, Self) {
+            (self.clone(), other.into())
+        }
+    }
+}
+
+#[test]
+pub fn rewrite_self_in_bounds() {
+    let jar = Jar(3);
+    assert_eq!(vec![Jar(3), Jar(3)], jar.dup().times(2));
+    assert_eq!((Jar(3), Jar(4)), jar.pair(4).collect());
+}
+
+trait Factory {
+    type Creator;
+
+    fn create() -> Self::Creator;
+}
+
+mod first_geometry {
+    pub struct Point;
+}
+
+mod second_geometry {
+    pub struct Point;
+}
+
+assemblist! {
+    impl Factory for Vec<u8> {
+        type Creator = vec_u8_create::Output;
+
+        fn create().filled(n: u8) -> Vec<u8> { vec![n; 2] }
+    }
+
+    impl Factory for Vec<u16> {
+        type Creator = vec_u16_create::Output;
+
+        fn create().filled(n: u16) -> Vec<u16> { vec![n; 3] }
+        fn create().doubled(n: u16) -> Vec<u16> { vec![2 * n; 3] }
+    }
+
+    impl Factory for first_geometry::Point {
+        type Creator = first_geometry_point_create::Output;
+
+        fn create().named() -> &'static str { "first point" }
+    }
+
+    impl Factory for second_geometry::Point {
+        type Creator = second_geometry_point_create::Output;
+
+        fn create().named() -> &'static str { "second point" }
+    }
+}
+
+#[test]
+pub fn decompose_trait_implementations_for_types_sharing_their_name() {
+    assert_eq!(vec![7u8, 7], <Vec<u8> as Factory>::create().filled(7));
+    assert_eq!(vec![7u16, 7, 7], <Vec<u16> as Factory>::create().filled(7));
+    assert_eq!(
+        vec![14u16, 14, 14],
+        <Vec<u16> as Factory>::create().doubled(7)
+    );
+    assert_eq!("first point", first_geometry::Point::create().named());
+    assert_eq!("second point", second_geometry::Point::create().named());
+}
@@ -0,0 +1,137 @@
+use assemblist::assemblist;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Item {
+    id: u64,
+    deleted: bool,
+}
+
+assemblist! {
+    pub trait Repository {
+        fn find(&self, id: u64).{
+            fn with_deleted(flag: bool) -> Option<Item>;
+            fn first() -> Option<Item> {
+                self.find_with_deleted(id, false)
+            }
+        }
+        fn count(&self).where_id().{
+            fn above(min: u64) -> usize;
+            fn below(max: u64) -> usize;
+        }
+        fn create(id: u64).deleted(flag: bool) -> Item;
+    }
+}
+
+struct Memory(Vec<Item>);
+
+impl Repository for Memory {
+    fn find_with_deleted(&self, id: u64, flag: bool) -> Option<Item> {
+        self.0
+            .iter()
+            .find(|item| item.id == id && (flag || !item.deleted))
+            .cloned()
+    }
+
+    fn count_where_id_above(&self, min: u64) -> usize {
+        self.0.iter().filter(|item| min < item.id).count()
+    }
+
+    fn count_where_id_below(&self, max: u64) -> usize {
+        self.0.iter().filter(|item| item.id < max).count()
+    }
+
+    fn create_deleted(id: u64, flag: bool) -> Item {
+        Item { id, deleted: flag }
+    }
+}
+
+#[test]
+pub fn call_method_chains_declared_in_trait() {
+    let memory = Memory(vec![
+        Memory::create(1).deleted(false),
+        Memory::create(2).deleted(true),
+        Memory::create(3).deleted(false),
+    ]);
+
+    assert_eq!(None, memory.find(2).first());
+    assert_eq!(
+        Some(Item {
+            id: 2,
+            deleted: true
+        }),
+        memory.find(2).with_deleted(true)
+    );
+    assert_eq!(2, memory.count().where_id().above(1));
+    assert_eq!(1, memory.count().where_id().below(2));
+}
+
+assemblist! {
+    trait Store<T> where T: Clone {
+        const NAME: &'static str;
+
+        fn put(&mut self, key: u8).value(value: T);
+        fn get(&self, key: u8).or(fallback: T) -> T;
+    }
+}
+
+impl<T: Clone> Store<T> for Vec<(u8, T)> {
+    const NAME: &'static str = "vec";
+
+    fn put_value(&mut self, key: u8, value: T) {
+        self.push((key, value));
+    }
+
+    fn get_or(&self, key: u8, fallback: T) -> T {
+        self.iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.clone())
+            .unwrap_or(fallback)
+    }
+}
+
+#[test]
+pub fn call_method_chains_declared_in_generic_trait() {
+    let mut store = Vec::<(u8, &str)>::new();
+    store.put(3).value("three");
+    assert_eq!("three", store.get(3).or("none"));
+    assert_eq!("none", store.get(4).or("none"));
+    assert_eq!("vec", <Vec<(u8, &str)> as Store<&str>>::NAME);
+}
+
+assemblist! {
+    pub trait Duplicate {
+        fn duplicate(&self).times(n: usize) -> Vec<Self> where Self: Clone {
+            vec![self.clone(); n]
+        }
+    }
+}
+
+impl Duplicate for Item {}
+
+#[test]
+pub fn call_method_chains_bounded_by_self() {
+    let item = Item {
+        id: 4,
+        deleted: false,
+    };
+    assert_eq!(vec![item.clone(), item.clone()], item.duplicate().times(2));
+}
+
+assemblist! {
+    pub trait Label {
+        #[assemblist(derive(Clone, Debug))]
+        fn label(id: u64).named(name: &'static str) -> String {
+            format!("#{id} {name}")
+        }
+    }
+}
+
+impl Label for Memory {}
+
+#[test]
+pub fn derive_traits_on_method_chains_declared_in_trait() {
+    let template = Memory::label(7);
+    assert!(format!("{template:?}").contains("id: 7"));
+    assert_eq!("#7 first", template.clone().named("first"));
+    assert_eq!("#7 second", template.named("second"));
+}
@@ -1,4 +1,6 @@
 use assemblist::assemblist;
+use std::fmt::Display;
+use std::str::FromStr;
 
 #[test]
 pub fn decompose_replacen() {
@@ -50,3 +52,124 @@ fn decompose_resize_with() {
 
     assert_eq!(a, vec![12, 54, -9, 0, 3434, 42, 42, 42]);
 }
+
+#[test]
+fn decompose_replacen_with_default_values() {
+    assemblist! {
+        pub fn replace_in<'a>(string: &'a str)
+            .occurrences_of(pattern: &'a str)
+            .with(to: &'a str = "")
+            .at_most(n: usize = string.len())
+            .times() -> String
+        {
+            string.replacen(pattern, to, n)
+        }
+    }
+
+    let text = "Ah! vous dirai-je, maman";
+
+    assert_eq!(
+        "h! vous dirai-je, maman",
+        replace_in(text).occurrences_of("A").times()
+    );
+    assert_eq!(
+        "Ah! vous dire-je, maman",
+        replace_in(text).occurrences_of("ai").with("e").times()
+    );
+    assert_eq!(
+        "Ah! vous diri-je, mmn",
+        replace_in(text).occurrences_of("a").at_most(5).times()
+    );
+    assert_eq!(
+        "Ah! vous dirAi-je, mAman",
+        replace_in(text)
+            .occurrences_of("a")
+            .with("A")
+            .at_most(2)
+            .times()
+    );
+}
+
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[test]
+fn decompose_with_argument_patterns() {
+    assemblist! {
+        pub fn translate(Point { x, y }: Point)
+            .by((dx, dy): (i32, i32))
+            .into(mut buffer: Vec<i32>) -> Vec<i32>
+        {
+            buffer.push(x + dx);
+            buffer.push(y + dy);
+            buffer
+        }
+    }
+
+    let result = translate(Point { x: 1, y: 2 }).by((10, 20)).into(vec![0]);
+
+    assert_eq!(result, vec![0, 11, 22]);
+
+    assemblist! {
+        fn sum().r#type((a, b): (u8, u8)) -> u8 { a + b }
+    }
+
+    assert_eq!(5, sum().r#type((2, 3)));
+}
+
+#[test]
+fn decompose_with_elided_lifetimes() {
+    assemblist! {
+        pub fn replace_in(text: &str)
+            .occurrences_of(pattern: &str)
+            .with(to: &str) -> String
+        {
+            text.replace(pattern, to)
+        }
+
+        pub fn skip_in(text: &str).first(n: usize) -> &str {
+            &text[n..]
+        }
+
+        pub fn first(x: u8).r#type(s: &str).done() -> usize {
+            x as usize + s.len()
+        }
+    }
+
+    assert_eq!("a-b-c", replace_in("a b c").occurrences_of(" ").with("-"));
+    assert_eq!("world", skip_in("hello world").first(6));
+    assert_eq!(8, first(3).r#type("hello").done());
+}
+
+#[test]
+fn decompose_with_impl_trait_arguments() {
+    assemblist! {
+        pub fn retain_from(items: impl IntoIterator<Item = u32>)
+            .matching(predicate: impl FnMut(&u32) -> bool)
+            .mapped_with(mapping: impl Fn(u32) -> String) -> Vec<String>
+        {
+            items.into_iter().filter(predicate).map(mapping).collect()
+        }
+    }
+
+    let result = retain_from(vec![1, 2, 3, 4])
+        .matching(|n| n % 2 == 0)
+        .mapped_with(|n| format!("#{n}"));
+
+    assert_eq!(result, vec!["#2".to_string(), "#4".to_string()]);
+}
+
+#[test]
+fn call_final_section_with_impl_trait_arguments_and_turbofish() {
+    assemblist! {
+        fn parse(text: &str).into_type<T: FromStr>(log: impl Display) -> Option<T> {
+            println!("{log}");
+            text.parse().ok()
+        }
+    }
+
+    assert_eq!(Some(3), parse("3").into_type::<u8>("x"));
+    assert_eq!(None, parse("three").into_type::<u8>(42));
+}